@@ -1,15 +1,25 @@
 use crate::{
-    command_parser::{self, ParseResult},
+    command_parser::{self, ParseError, ParseResult, Selected, Selection, TaskRef},
+    config,
     config::{
-        C_ADD, C_CLEAR, C_DONE, C_EDIT, C_EXIT, C_HELP, C_LIST, C_LOAD, C_REMOVE, C_SAVE, C_UNDONE,
+        C_ADD, C_ALL, C_ARCHIVE, C_BACKUPS, C_BLOCK, C_CANCEL, C_CLEAR, C_CONTEXTS, C_CREATE,
+        C_DELETE, C_DONE, C_DUE, C_EDIT, C_EMPTY, C_EXIT, C_FIND, C_HELP, C_LIST, C_LISTS, C_LOAD,
+        C_MOVE, C_NEXT, C_NONE, C_ON, C_OVERDUE, C_PRIO, C_PROJECTS, C_REDO, C_REMOVE, C_REOPEN,
+        C_REPEAT, C_RESTORE, C_SAVE, C_SEARCH, C_START, C_TO, C_TODAY, C_TRASH, C_UNARCHIVE,
+        C_UNBLOCK, C_UNDO, C_UNDONE, C_USE, C_VIEW, C_WAIT, DEFAULT_LIST_NAME, O_DONE, O_LIMIT,
+        O_LONG, O_OLDER, O_PENDING, O_REVERSE, O_SORT, O_UNDER,
     },
+    date,
     models::{
-        list::{self, List},
+        list::{self, Format, List, SortKey, TaskStatus},
+        priority::Priority,
+        recurrence::Recurrence,
         state::{State, Status},
     },
-    utils,
+    query, storage, utils,
 };
-use std::fs;
+use regex::Regex;
+use std::{fs, path::Path};
 
 #[derive(Debug, PartialEq)]
 pub enum ActionResult {
@@ -21,19 +31,276 @@ pub enum ActionResult {
     TaskNotFound,
     FileReadError,
     UnknownCommand,
-    InvalidArguments,
+    InvalidArguments(ParseError),
     NeedConfirm,
+    NeedConfirmTasks(String),
+    NeedConfirmSubtasks(String),
     NeedFilePath,
     NeedTask,
     CannotSave,
     CannotLoad,
+    BackupNotFound,
+    ViewNotFound,
+    NothingToUndo,
+    NothingToRedo,
+    NothingToArchive,
+    ListNotFound,
+    ListExists,
+    ListInUse,
+    InteractiveOnly(String),
+    InvalidInput(ParseError),
+    DependencyCycle,
+    Blocked(String),
     Feedback(String),
 }
 
+impl ActionResult {
+    pub fn message(&self) -> Option<String> {
+        let message = match self {
+            ActionResult::Sh => return None,
+            ActionResult::Ok => "ok",
+            ActionResult::Terminate => "bye!",
+            ActionResult::ListFull => "list full",
+            ActionResult::ListEmpty => "list empty",
+            ActionResult::TaskNotFound => "task not found",
+            ActionResult::FileReadError => "file read error (cannot parse)",
+            ActionResult::UnknownCommand => "unknown command",
+            ActionResult::InvalidArguments(e) => return Some(format!("invalid arguments: {e}")),
+            ActionResult::NeedConfirm => "confirm? (y/n)",
+            ActionResult::NeedConfirmTasks(tasks) => return Some(format!("{tasks}confirm? (y/n)")),
+            ActionResult::NeedConfirmSubtasks(tasks) => {
+                return Some(format!("{tasks}complete subtasks too? (y/n)"))
+            }
+            ActionResult::NeedFilePath => "provide file path",
+            ActionResult::NeedTask => "enter task",
+            ActionResult::CannotSave => "cannot save",
+            ActionResult::CannotLoad => "cannot load",
+            ActionResult::BackupNotFound => "backup not found",
+            ActionResult::ViewNotFound => "view not found",
+            ActionResult::NothingToUndo => "nothing to undo",
+            ActionResult::NothingToRedo => "nothing to redo",
+            ActionResult::NothingToArchive => "nothing to archive",
+            ActionResult::ListNotFound => "list not found",
+            ActionResult::ListExists => "list already exists",
+            ActionResult::ListInUse => "list in use",
+            ActionResult::InteractiveOnly(command) => {
+                return Some(format!("{command} only works in an interactive session"))
+            }
+            ActionResult::InvalidInput(e) => return Some(e.to_string()),
+            ActionResult::DependencyCycle => "dependency cycle",
+            ActionResult::Blocked(tasks) => {
+                return Some(format!("blocked by:\n{}", tasks.trim_end()))
+            }
+            ActionResult::Feedback(feedback) => feedback,
+        };
+
+        Some(message.to_string())
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ActionResult::Sh
+            | ActionResult::Ok
+            | ActionResult::Terminate
+            | ActionResult::Feedback(..) => 0,
+            ActionResult::ListFull => 2,
+            ActionResult::ListEmpty => 3,
+            ActionResult::TaskNotFound => 4,
+            ActionResult::FileReadError => 5,
+            ActionResult::UnknownCommand => 6,
+            ActionResult::InvalidArguments(..) => 7,
+            ActionResult::NeedConfirm
+            | ActionResult::NeedConfirmTasks(..)
+            | ActionResult::NeedConfirmSubtasks(..) => 8,
+            ActionResult::NeedFilePath => 9,
+            ActionResult::NeedTask => 10,
+            ActionResult::CannotSave => 11,
+            ActionResult::CannotLoad => 12,
+            ActionResult::NothingToUndo => 13,
+            ActionResult::NothingToRedo => 14,
+            ActionResult::BackupNotFound => 15,
+            ActionResult::InvalidInput(..) => 16,
+            ActionResult::DependencyCycle => 17,
+            ActionResult::Blocked(..) => 18,
+            ActionResult::NothingToArchive => 19,
+            ActionResult::ListNotFound => 20,
+            ActionResult::ListExists => 21,
+            ActionResult::ListInUse => 22,
+            ActionResult::InteractiveOnly(..) => 23,
+            ActionResult::ViewNotFound => 24,
+        }
+    }
+}
+
 // =========== Helpers ===========
 
-fn extract_index_from_args(args: &Vec<&str>) -> usize {
-    args.first().unwrap().parse::<usize>().unwrap() - 1
+fn resolve_ref(task_ref: TaskRef, list: &List) -> Result<usize, ActionResult> {
+    match task_ref {
+        TaskRef::Position(position) => list.position_at(&[position]),
+        TaskRef::Path(path) => list.position_at(&path),
+        TaskRef::Id(id) => list.position_of(id),
+    }
+    .ok_or(ActionResult::TaskNotFound)
+}
+
+fn resolve_index(arg: &str, list: &List) -> Result<usize, ActionResult> {
+    match command_parser::parse_task_ref(arg) {
+        Ok(task_ref) => self::resolve_ref(task_ref, list),
+        Err(e) => Err(ActionResult::InvalidArguments(e)),
+    }
+}
+
+// Resolves a selection to distinct task indices, kept in the order given.
+fn resolve_selection(args: &[&str], list: &List) -> Result<Vec<usize>, ActionResult> {
+    let selected = match command_parser::parse_selection(args) {
+        Ok(Selection::All) if list.indices().is_empty() => return Err(ActionResult::ListEmpty),
+        Ok(Selection::All) => return Ok(list.indices()),
+        Ok(Selection::Tasks(selected)) => selected,
+        Err(e) => return Err(ActionResult::InvalidArguments(e)),
+    };
+
+    let mut indices: Vec<usize> = Vec::new();
+
+    for piece in selected {
+        let found = match piece {
+            Selected::One(task_ref) => vec![self::resolve_ref(task_ref, list)?],
+            // Ranges span top-level tasks.
+            Selected::Range(from, to) => (from..=to)
+                .map(|p| self::resolve_ref(TaskRef::Position(p), list))
+                .collect::<Result<Vec<usize>, ActionResult>>()?,
+        };
+
+        found.into_iter().for_each(|i| {
+            if !indices.contains(&i) {
+                indices.push(i);
+            }
+        });
+    }
+
+    Ok(indices)
+}
+
+// Reads `TASK` or `TASK on TASK`, as taken by `block` and `unblock`.
+fn resolve_dependency(args: &[&str], list: &List) -> Result<(usize, Option<usize>), ActionResult> {
+    let invalid = |e| Err(ActionResult::InvalidArguments(e));
+
+    match args {
+        [] => invalid(ParseError::MissingArgument("task")),
+        [task] => Ok((self::resolve_index(task, list)?, None)),
+        [_, C_ON] => invalid(ParseError::MissingArgument("blocking task")),
+        [task, C_ON, on] => Ok((
+            self::resolve_index(task, list)?,
+            Some(self::resolve_index(on, list)?),
+        )),
+        [_, C_ON, _, extra, ..] | [_, extra, ..] => {
+            invalid(ParseError::UnexpectedArgument(extra.to_string()))
+        }
+    }
+}
+
+// The open tasks, other than those at `indices`, that keep any of them
+// waiting.
+fn blocked(indices: &[usize], list: &List) -> Option<ActionResult> {
+    let mut blockers: Vec<usize> = Vec::new();
+
+    indices
+        .iter()
+        .flat_map(|i| list.blockers(*i))
+        .for_each(|i| {
+            if !indices.contains(&i) && !blockers.contains(&i) {
+                blockers.push(i);
+            }
+        });
+
+    match blockers.is_empty() {
+        true => None,
+        false => Some(ActionResult::Blocked(
+            list.to_view(&blockers, date::today()),
+        )),
+    }
+}
+
+// Subtasks of the tasks at `indices` that are not among them, each once.
+fn subtasks_of(indices: &[usize], list: &List) -> Vec<usize> {
+    let mut subtasks: Vec<usize> = Vec::new();
+
+    indices
+        .iter()
+        .flat_map(|i| list.subtasks(*i))
+        .for_each(|i| {
+            if !indices.contains(&i) && !subtasks.contains(&i) {
+                subtasks.push(i);
+            }
+        });
+
+    subtasks
+}
+
+// Like `subtasks_of`, only those still left to do.
+fn open_subtasks_of(indices: &[usize], list: &mut List) -> Vec<usize> {
+    let mut subtasks = self::subtasks_of(indices, list);
+    subtasks.retain(|i| list.get(*i).is_ok_and(|t| t.is_open()));
+    subtasks
+}
+
+#[derive(Default)]
+struct ListArgs<'a> {
+    done: Option<bool>,
+    sort: Option<SortKey>,
+    reverse: bool,
+    limit: Option<usize>,
+    long: bool,
+    tags: Vec<&'a str>,
+}
+
+// Every option may be given once, `prio` is short for `--sort priority`.
+fn parse_list_args(parse_result: &ParseResult) -> Result<ListArgs<'_>, ParseError> {
+    let mut list_args = ListArgs::default();
+    let mut args = parse_result.arguments.iter().enumerate();
+
+    while let Some((i, arg)) = args.next() {
+        let arg = arg.as_str();
+        let unexpected = || ParseError::UnexpectedArgument(arg.to_string());
+
+        if !parse_result.is_option(i) {
+            match arg {
+                C_PRIO if list_args.sort.is_none() => list_args.sort = Some(SortKey::Priority),
+                C_PRIO => return Err(unexpected()),
+                tag if tag.starts_with(['+', '@']) && tag.len() > 1 => list_args.tags.push(tag),
+                _ => return Err(ParseError::InvalidTag(arg.to_string())),
+            }
+
+            continue;
+        }
+
+        let repeated = match arg {
+            O_PENDING | O_DONE => list_args.done.replace(arg == O_DONE).is_some(),
+            O_REVERSE => std::mem::replace(&mut list_args.reverse, true),
+            O_LONG => std::mem::replace(&mut list_args.long, true),
+            O_SORT => {
+                let key = args
+                    .next()
+                    .ok_or(ParseError::MissingArgument("sort key"))?
+                    .1;
+                let key = key
+                    .parse::<SortKey>()
+                    .map_err(|_| ParseError::InvalidSortKey(key.to_string()))?;
+                list_args.sort.replace(key).is_some()
+            }
+            O_LIMIT => {
+                let limit = args.next().ok_or(ParseError::MissingArgument("limit"))?.1;
+                let limit = command_parser::parse_number::<usize>(limit)?;
+                list_args.limit.replace(limit).is_some()
+            }
+            _ => return Err(ParseError::UnknownOption(arg.to_string())),
+        };
+
+        if repeated {
+            return Err(unexpected());
+        }
+    }
+
+    Ok(list_args)
 }
 
 fn map_list_error(e: list::Error) -> ActionResult {
@@ -41,6 +308,10 @@ fn map_list_error(e: list::Error) -> ActionResult {
         list::Error::CapacityExceeded => ActionResult::ListFull,
         list::Error::ItemNotFound => ActionResult::TaskNotFound,
         list::Error::InvalidPattern => ActionResult::FileReadError,
+        list::Error::UnsupportedVersion => ActionResult::FileReadError,
+        list::Error::NothingToUndo => ActionResult::NothingToUndo,
+        list::Error::NothingToRedo => ActionResult::NothingToRedo,
+        list::Error::DependencyCycle => ActionResult::DependencyCycle,
     }
 }
 
@@ -55,43 +326,399 @@ fn help() -> ActionResult {
         "{C_EXIT}     - Exit program
 {C_HELP}     - View available commands
 {C_LIST}     - View all tasks
+{C_LIST} {C_PRIO} - View all tasks sorted by priority
+{C_LIST} +work - View tasks of a +project or @context
+{C_LIST} {O_PENDING} {O_SORT} due {O_LIMIT} 5 - Filter ({O_PENDING}, {O_DONE}) and sort (text, created, due, priority; {O_REVERSE})
+{C_LIST} {O_LONG} - View tasks with when they were created and completed
+{C_FIND} milk - Find tasks containing text, or matching a regex with {C_FIND} '/^buy\\s/'
 {C_CLEAR}    - Clear tasks
-{C_ADD}      - Add new task
-{C_EDIT} 2   - Edit task by index where 2 is index
+{C_ARCHIVE}  - Move done tasks to the archive (or {C_ARCHIVE} {O_OLDER} 30 for those done over 30 days ago)
+{C_ARCHIVE} {C_LIST} - View archived tasks ({C_ARCHIVE} {C_SEARCH} milk finds some)
+{C_UNARCHIVE} 2 - Move archived task back to the list where 2 is its index in the archive
+{C_UNDO}     - Undo last change
+{C_REDO}     - Redo last undone change
+{C_ADD}      - Add new task (or {C_ADD} buy milk)
+{C_ADD} {O_UNDER} 3 - Add a subtask to task 3 (or {C_ADD} {O_UNDER} 3 buy milk)
+{C_EDIT} 2   - Edit task by index where 2 is index (or {C_EDIT} 2 new text)
 {C_REMOVE} 2 - Delete task by index where 2 is index
+{C_TRASH}    - View removed tasks ({C_TRASH} {C_EMPTY} purges them)
+{C_RESTORE} 2 - Put removed task back where 2 is its index in the trash
 {C_DONE} 2   - Mark task as DONE where 2 is index
 {C_UNDONE} 2 - Mark task as UNDONE where 2 is index
-{C_SAVE}     - Save list to file
-{C_LOAD}     - Load list from file"
+{C_START} 2  - Mark task as started (also {C_WAIT}, {C_CANCEL} and {C_REOPEN})
+{C_BLOCK} 5 {C_ON} 3 - Make task 5 wait for task 3 (undo with {C_UNBLOCK} 5 {C_ON} 3, or {C_UNBLOCK} 5 for all)
+{C_NEXT}     - View tasks that can be worked on now
+{C_PRIO} 2 high - Set priority (none/low/medium/high) where 2 is index
+{C_DUE} 2 fri - Set due date (2026-11-01, tomorrow, next fri, +3d, none) where 2 is index
+{C_REPEAT} 2 weekly:mon - Repeat task when done (daily, weekly, weekly:mon,thu, monthly:15, after:3d, none)
+{C_TODAY}    - View tasks due today
+{C_OVERDUE}  - View overdue tasks
+{C_PROJECTS} - View all +projects with task counts
+{C_CONTEXTS} - View all @contexts with task counts
+{C_SAVE}     - Save list to file (or {C_SAVE} ~/todo.txt)
+{C_LOAD}     - Load list from file (or {C_LOAD} ~/todo.txt)
+{C_VIEW}     - View saved views ({C_VIEW} weekly shows one)
+{C_VIEW} {C_ADD} weekly \"status:pending and (tag:work or prio>=high) and due<+7d\" - Save a view
+{C_VIEW} {C_REMOVE} weekly - Delete a view
+{C_LISTS}    - View lists, the one in use marked with *
+{C_USE} work - Switch to another list (or {C_USE} {DEFAULT_LIST_NAME})
+{C_LIST} {C_CREATE} groceries - Create a list ({C_LIST} {C_DELETE} groceries deletes one)
+{C_MOVE} 3 {C_TO} groceries - Move task and its subtasks to another list
+{C_BACKUPS}  - View backups of the list in use
+{C_BACKUPS} {C_RESTORE} 2 - Restore backup where 2 is backup index
+
+Tasks are addressed by index (2), subtask path (3.2) or by id (#7)
+{C_REMOVE}, {C_DONE}, {C_UNDONE}, {C_START}, {C_WAIT}, {C_CANCEL} and {C_REOPEN} take several (1 3, 2-6, 4,7-9 or {C_ALL})"
     );
 
     ActionResult::Feedback(feedback.to_string())
 }
 
-fn list(l: &mut List) -> ActionResult {
-    ActionResult::Feedback(l.to_text())
+fn list(parse_result: ParseResult, l: &mut List, state: &mut State) -> ActionResult {
+    if let [action @ (C_CREATE | C_DELETE), args @ ..] = parse_result.args().as_slice() {
+        return self::change_lists(action, args, l, state);
+    }
+
+    let ListArgs {
+        done,
+        sort,
+        reverse,
+        limit,
+        long,
+        tags,
+    } = match self::parse_list_args(&parse_result) {
+        Ok(args) => args,
+        Err(e) => return ActionResult::InvalidArguments(e),
+    };
+
+    let mut indices = match done {
+        Some(is_done) => l.indices_done(is_done),
+        None => l.indices(),
+    };
+
+    if !tags.is_empty() {
+        let tagged = l.indices_tagged(&tags);
+        indices.retain(|i| tagged.contains(i));
+    }
+
+    if let Some(key) = sort {
+        l.sort_indices(&mut indices, key);
+    }
+
+    if reverse {
+        indices.reverse();
+    }
+
+    if let Some(limit) = limit {
+        indices.truncate(limit);
+    }
+
+    match long {
+        true => ActionResult::Feedback(l.to_long_view(&indices, date::today())),
+        false => ActionResult::Feedback(l.to_view(&indices, date::today())),
+    }
+}
+
+fn tag_counts(prefix: &str, counts: Vec<(String, usize)>) -> ActionResult {
+    let mut result = String::new();
+
+    counts.iter().for_each(|(tag, count)| {
+        result.push_str(&format!("{prefix}{tag} ({count})\n"));
+    });
+
+    ActionResult::Feedback(result)
+}
+
+fn projects(list: &mut List) -> ActionResult {
+    self::tag_counts("+", list.projects())
+}
+
+fn contexts(list: &mut List) -> ActionResult {
+    self::tag_counts("@", list.contexts())
+}
+
+// Plain queries match anywhere in the text ignoring case, `/.../` is a regex.
+fn find(parse_result: ParseResult, list: &mut List, state: &State) -> ActionResult {
+    self::search(parse_result.rest(), list, false, state.highlight)
+}
+
+// Tasks containing `query`, or matching it as a regex when written `/.../`,
+// shown with their times when `long`.
+fn search(query: &str, list: &List, long: bool, highlight: bool) -> ActionResult {
+    let pattern = match query.strip_prefix('/').and_then(|q| q.strip_suffix('/')) {
+        _ if query.is_empty() => {
+            return ActionResult::InvalidArguments(ParseError::MissingArgument("query"))
+        }
+        Some(regex) => Regex::new(regex),
+        None => Regex::new(&format!("(?i){}", regex::escape(query))),
+    };
+
+    match pattern {
+        Ok(pattern) => {
+            let indices = list.indices_matching(&pattern);
+            let today = date::today();
+
+            ActionResult::Feedback(match (highlight, long) {
+                (true, _) => list.to_highlighted_view(&indices, today, long, &pattern),
+                (false, true) => list.to_long_view(&indices, today),
+                (false, false) => list.to_view(&indices, today),
+            })
+        }
+        Err(..) => ActionResult::InvalidArguments(ParseError::InvalidRegex(query.to_string())),
+    }
+}
+
+// `archive` puts away done tasks, `archive --older 30` only those done more
+// than 30 days ago. `archive list` and `archive search milk` look through
+// what was put away.
+fn archive(parse_result: ParseResult, list: &mut List, state: &State) -> ActionResult {
+    let invalid = ActionResult::InvalidArguments;
+    let today = date::today();
+
+    let before = match parse_result.args().as_slice() {
+        [] => None,
+        [O_OLDER, days] => match command_parser::parse_number::<u32>(days) {
+            // Nothing was done before the first date there is.
+            Ok(days) => match today.add_days(-i64::from(days)) {
+                Some(before) => Some(before),
+                None => return ActionResult::NothingToArchive,
+            },
+            Err(e) => return invalid(e),
+        },
+        [C_LIST] => {
+            let archived = list.archived();
+            return ActionResult::Feedback(archived.to_long_view(&archived.indices(), today));
+        }
+        [C_SEARCH, ..] => {
+            let query = parse_result.rest_after(1);
+            return self::search(query, &list.archived(), true, state.highlight);
+        }
+        [O_OLDER] => return invalid(ParseError::MissingArgument("days")),
+        [O_OLDER, _, extra, ..] | [C_LIST, extra, ..] | [extra, ..] => {
+            return invalid(ParseError::UnexpectedArgument(extra.to_string()))
+        }
+    };
+
+    let indices = list.indices_archivable(before);
+
+    if indices.is_empty() {
+        return ActionResult::NothingToArchive;
+    }
+
+    match list.archive(&indices) {
+        Ok(()) => ActionResult::Ok,
+        Err(e) => map_list_error(e),
+    }
+}
+
+// Tasks are addressed as `archive list` shows them.
+fn unarchive(parse_result: ParseResult, list: &mut List) -> ActionResult {
+    let index = match parse_result.args().as_slice() {
+        [] => return ActionResult::InvalidArguments(ParseError::MissingArgument("task")),
+        [task] => match self::resolve_index(task, &list.archived()) {
+            Ok(index) => index,
+            Err(result) => return result,
+        },
+        [_, extra, ..] => {
+            return ActionResult::InvalidArguments(ParseError::UnexpectedArgument(
+                extra.to_string(),
+            ))
+        }
+    };
+
+    match list.unarchive(index) {
+        Ok(()) => ActionResult::Ok,
+        Err(e) => map_list_error(e),
+    }
+}
+
+// `trash` lists removed tasks, newest first, and `trash empty` purges them.
+// `trash restore 2` puts back entry 2, numbered as `trash` shows them.
+fn trash(parse_result: ParseResult, list: &mut List) -> ActionResult {
+    let invalid = ActionResult::InvalidArguments;
+
+    match parse_result.args().as_slice() {
+        [] => ActionResult::Feedback(list.to_trash_view()),
+        [C_EMPTY] => {
+            list.empty_trash();
+            ActionResult::Ok
+        }
+        [C_RESTORE, args @ ..] => self::restore(args, list),
+        [C_EMPTY, extra, ..] | [extra, ..] => {
+            invalid(ParseError::UnexpectedArgument(extra.to_string()))
+        }
+    }
+}
+
+// `restore N`, also reachable as `trash restore N`.
+fn restore(args: &[&str], list: &mut List) -> ActionResult {
+    let invalid = ActionResult::InvalidArguments;
+
+    match args {
+        [] => invalid(ParseError::MissingArgument("entry")),
+        [entry] => match command_parser::parse_position(entry) {
+            Ok(position) => match list.restore_from_trash(position - 1) {
+                Ok(()) => ActionResult::Ok,
+                Err(e) => map_list_error(e),
+            },
+            Err(e) => invalid(e),
+        },
+        [_, extra, ..] => invalid(ParseError::UnexpectedArgument(extra.to_string())),
+    }
+}
+
+fn is_view_name(name: &str) -> bool {
+    !name.is_empty()
+        && name != C_ADD
+        && name != C_REMOVE
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+}
+
+// `view` lists the saved views, `view NAME` shows the tasks matching one,
+// `view add NAME EXPR` and `view remove NAME` change the config file.
+fn view(parse_result: ParseResult, list: &mut List, state: &mut State) -> ActionResult {
+    let invalid = ActionResult::InvalidArguments;
+
+    match parse_result.args().as_slice() {
+        [] => {
+            let mut result = String::new();
+
+            state.views.iter().for_each(|(name, expression)| {
+                result.push_str(&format!("{name}: {expression}\n"));
+            });
+
+            ActionResult::Feedback(result)
+        }
+        [C_ADD] => invalid(ParseError::MissingArgument("view name")),
+        [C_ADD, _] => invalid(ParseError::MissingArgument("query")),
+        [C_ADD, name, ..] => {
+            if !self::is_view_name(name) {
+                return invalid(ParseError::InvalidViewName(name.to_string()));
+            }
+
+            let expression = parse_result.rest_after(2);
+
+            // Each view is one line of the config file.
+            if let Some(c) = expression.chars().find(|c| *c == '\n' || *c == '\r') {
+                let e = query::Error::UnexpectedToken(c.to_string());
+                return invalid(ParseError::InvalidQuery(e));
+            }
+
+            if let Err(e) = query::parse(expression) {
+                return invalid(ParseError::InvalidQuery(e));
+            }
+
+            let Some(path) = &state.config_path else {
+                return ActionResult::CannotSave;
+            };
+
+            match config::write_view(path, name, Some(expression)) {
+                Ok(()) => {
+                    match state.views.iter_mut().find(|(n, _)| n == name) {
+                        Some(view) => view.1 = expression.to_string(),
+                        None => state.views.push((name.to_string(), expression.to_string())),
+                    }
+
+                    ActionResult::Ok
+                }
+                Err(..) => ActionResult::CannotSave,
+            }
+        }
+        [C_REMOVE] => invalid(ParseError::MissingArgument("view name")),
+        [C_REMOVE, name] => {
+            let Some(position) = state.views.iter().position(|(n, _)| n == name) else {
+                return ActionResult::ViewNotFound;
+            };
+
+            let Some(path) = &state.config_path else {
+                return ActionResult::CannotSave;
+            };
+
+            match config::write_view(path, name, None) {
+                Ok(()) => {
+                    state.views.remove(position);
+                    ActionResult::Ok
+                }
+                Err(..) => ActionResult::CannotSave,
+            }
+        }
+        [name] => {
+            let Some((_, expression)) = state.views.iter().find(|(n, _)| n == name) else {
+                return ActionResult::ViewNotFound;
+            };
+
+            match query::parse(expression) {
+                Ok(query) => {
+                    let today = date::today();
+                    let indices = list.indices_filtered(|t| query.matches(t, list, today));
+                    ActionResult::Feedback(list.to_view(&indices, today))
+                }
+                Err(e) => invalid(ParseError::InvalidQuery(e)),
+            }
+        }
+        [_, extra, ..] => invalid(ParseError::UnexpectedArgument(extra.to_string())),
+    }
 }
 
-fn add(state: &mut State) -> ActionResult {
-    state.set(C_ADD, Status::NeedPlainText, None);
+// `add --under 3 ...` adds a subtask to task 3.
+fn add(parse_result: ParseResult, list: &mut List, state: &mut State) -> ActionResult {
+    let (parent, text) = match parse_result.args().as_slice() {
+        [O_UNDER, ..] if parse_result.is_option(0) => match parse_result.args().get(1) {
+            Some(arg) => match self::resolve_index(arg, list) {
+                Ok(index) => (Some(index), parse_result.rest_after(2)),
+                Err(result) => return result,
+            },
+            None => return ActionResult::InvalidArguments(ParseError::MissingArgument("task")),
+        },
+        _ => (None, parse_result.rest()),
+    };
+
+    if !text.is_empty() {
+        return self::add_task(text.to_string(), parent, list);
+    }
+
+    state.set(C_ADD, Status::NeedPlainText, parent);
     ActionResult::NeedTask
 }
 
-fn add_text(text: String, list: &mut List, state: &mut State) -> ActionResult {
+fn add_text(raw_input: String, list: &mut List, state: &mut State) -> ActionResult {
+    let parent = state.task_index;
     state.reset();
 
-    match list.add(text) {
+    self::add_task(raw_input, parent, list)
+}
+
+fn add_task(text: String, parent: Option<usize>, list: &mut List) -> ActionResult {
+    let result = match parent {
+        Some(parent) => list.add_subtask(parent, text),
+        None => list.add(text),
+    };
+
+    match result {
         Ok(()) => ActionResult::Ok,
         Err(e) => map_list_error(e),
     }
 }
 
 fn edit(parse_result: ParseResult, list: &mut List, state: &mut State) -> ActionResult {
-    if !utils::are_strings_integers(&parse_result.arguments) {
-        return ActionResult::InvalidArguments;
-    }
+    let index = match parse_result.args().first() {
+        Some(arg) => match self::resolve_index(arg, list) {
+            Ok(index) => index,
+            Err(result) => return result,
+        },
+        None => return ActionResult::InvalidArguments(ParseError::MissingArgument("task")),
+    };
 
-    let index = self::extract_index_from_args(&parse_result.arguments);
+    let text = parse_result.rest_after(1);
+
+    if !text.is_empty() {
+        return match list.alter(index, text.to_string()) {
+            Ok(()) => ActionResult::Ok,
+            Err(e) => map_list_error(e),
+        };
+    }
 
     match list.get(index) {
         Ok(..) => {
@@ -117,18 +744,38 @@ fn edit_text(raw_input: String, list: &mut List, state: &mut State) -> ActionRes
 }
 
 fn remove(parse_result: ParseResult, list: &mut List, state: &mut State) -> ActionResult {
-    if !utils::are_strings_integers(&parse_result.arguments) {
-        return ActionResult::InvalidArguments;
-    }
-
-    let index = self::extract_index_from_args(&parse_result.arguments);
-
-    match list.get(index) {
-        Ok(..) => {
-            state.set(C_REMOVE, Status::NeedConfirmation, Some(index));
-            ActionResult::NeedConfirm
+    let indices = match self::resolve_selection(&parse_result.args(), list) {
+        Ok(indices) => indices,
+        Err(result) => return result,
+    };
+
+    let subtasks = self::subtasks_of(&indices, list);
+
+    match indices.as_slice() {
+        [index] if subtasks.is_empty() => match list.get(*index) {
+            Ok(..) => {
+                state.set(C_REMOVE, Status::NeedConfirmation, Some(*index));
+                ActionResult::NeedConfirm
+            }
+            Err(e) => map_list_error(e),
+        },
+        _ => {
+            // Subtasks go with their parent, so they are listed under it.
+            let mut shown: Vec<usize> = Vec::new();
+
+            indices
+                .iter()
+                .flat_map(|i| std::iter::once(*i).chain(list.subtasks(*i)))
+                .for_each(|i| {
+                    if !shown.contains(&i) {
+                        shown.push(i);
+                    }
+                });
+
+            let tasks = list.to_view(&shown, date::today());
+            state.set_many(C_REMOVE, Status::NeedConfirmation, indices);
+            ActionResult::NeedConfirmTasks(tasks)
         }
-        Err(e) => map_list_error(e),
     }
 }
 
@@ -138,45 +785,228 @@ fn remove_confirm(raw_input: String, list: &mut List, state: &mut State) -> Acti
         return ActionResult::Sh;
     }
 
-    if let Some(index) = state.task_index {
-        state.reset();
+    let indices: Vec<usize> = state
+        .task_index
+        .into_iter()
+        .chain(state.task_indices.iter().copied())
+        .collect();
+    state.reset();
 
-        match list.remove(index) {
-            Ok(()) => ActionResult::Ok,
-            Err(e) => map_list_error(e),
-        }
-    } else {
-        state.reset();
-        ActionResult::TaskNotFound
+    if indices.is_empty() {
+        return ActionResult::TaskNotFound;
+    }
+
+    match list.remove(&indices) {
+        Ok(()) => ActionResult::Ok,
+        Err(e) => map_list_error(e),
+    }
+}
+
+// Refuses while the tasks wait for open ones, and asks first whether
+// pending subtasks of the tasks should be done too.
+fn done(parse_result: ParseResult, list: &mut List, state: &mut State) -> ActionResult {
+    let indices = match self::resolve_selection(&parse_result.args(), list) {
+        Ok(indices) => indices,
+        Err(result) => return result,
+    };
+
+    if let Some(result) = self::blocked(&indices, list) {
+        return result;
+    }
+
+    let pending = self::open_subtasks_of(&indices, list);
+
+    if !pending.is_empty() {
+        let tasks = list.to_view(&pending, date::today());
+        state.set_many(C_DONE, Status::NeedConfirmation, indices);
+        return ActionResult::NeedConfirmSubtasks(tasks);
+    }
+
+    match list.mark_done(&indices) {
+        Ok(()) => ActionResult::Ok,
+        Err(e) => map_list_error(e),
     }
 }
 
-fn done(parse_result: ParseResult, list: &mut List) -> ActionResult {
-    if !utils::are_strings_integers(&parse_result.arguments) {
-        return ActionResult::InvalidArguments;
+// Either answer completes the tasks, a yes their subtasks as well.
+// Anything else asks again.
+fn done_confirm(raw_input: String, list: &mut List, state: &mut State) -> ActionResult {
+    let mut indices = state.task_indices.clone();
+    let pending = self::open_subtasks_of(&indices, list);
+
+    if command_parser::is_confirm(&raw_input) {
+        indices.extend(pending);
+    } else if !command_parser::is_deny(&raw_input) {
+        return ActionResult::NeedConfirmSubtasks(list.to_view(&pending, date::today()));
     }
 
-    let index = self::extract_index_from_args(&parse_result.arguments);
+    state.reset();
+
+    if let Some(result) = self::blocked(&indices, list) {
+        return result;
+    }
 
-    match list.mark_done(index) {
+    match list.mark_done(&indices) {
         Ok(()) => ActionResult::Ok,
         Err(e) => map_list_error(e),
     }
 }
 
 fn undone(parse_result: ParseResult, list: &mut List) -> ActionResult {
-    if !utils::are_strings_integers(&parse_result.arguments) {
-        return ActionResult::InvalidArguments;
+    let indices = match self::resolve_selection(&parse_result.args(), list) {
+        Ok(indices) => indices,
+        Err(result) => return result,
+    };
+
+    match list.mark_undone(&indices) {
+        Ok(()) => ActionResult::Ok,
+        Err(e) => map_list_error(e),
+    }
+}
+
+// Tasks still waiting for others cannot be started.
+fn set_status(parse_result: ParseResult, list: &mut List, status: TaskStatus) -> ActionResult {
+    let indices = match self::resolve_selection(&parse_result.args(), list) {
+        Ok(indices) => indices,
+        Err(result) => return result,
+    };
+
+    if status == TaskStatus::Started {
+        if let Some(result) = self::blocked(&indices, list) {
+            return result;
+        }
+    }
+
+    match list.set_status(&indices, status) {
+        Ok(()) => ActionResult::Ok,
+        Err(e) => map_list_error(e),
+    }
+}
+
+fn block(parse_result: ParseResult, list: &mut List) -> ActionResult {
+    let (index, on) = match self::resolve_dependency(&parse_result.args(), list) {
+        Ok((index, Some(on))) => (index, on),
+        Ok((_, None)) => {
+            return ActionResult::InvalidArguments(ParseError::MissingArgument("blocking task"))
+        }
+        Err(result) => return result,
+    };
+
+    match list.block(index, on) {
+        Ok(()) => ActionResult::Ok,
+        Err(e) => map_list_error(e),
+    }
+}
+
+fn unblock(parse_result: ParseResult, list: &mut List) -> ActionResult {
+    let (index, on) = match self::resolve_dependency(&parse_result.args(), list) {
+        Ok(dependency) => dependency,
+        Err(result) => return result,
+    };
+
+    match list.unblock(index, on) {
+        Ok(()) => ActionResult::Ok,
+        Err(e) => map_list_error(e),
+    }
+}
+
+fn prio(parse_result: ParseResult, list: &mut List) -> ActionResult {
+    let (index, priority) = match parse_result.args().as_slice() {
+        [index, priority] => (*index, priority.to_string()),
+        [] => return ActionResult::InvalidArguments(ParseError::MissingArgument("task")),
+        [_] => return ActionResult::InvalidArguments(ParseError::MissingArgument("priority")),
+        [_, _, extra, ..] => {
+            return ActionResult::InvalidArguments(ParseError::UnexpectedArgument(
+                extra.to_string(),
+            ))
+        }
+    };
+
+    let index = match self::resolve_index(index, list) {
+        Ok(index) => index,
+        Err(result) => return result,
+    };
+
+    match priority.parse::<Priority>() {
+        Ok(priority) => match list.set_priority(index, priority) {
+            Ok(()) => ActionResult::Ok,
+            Err(e) => map_list_error(e),
+        },
+        Err(..) => ActionResult::InvalidArguments(ParseError::InvalidPriority(priority)),
+    }
+}
+
+fn due(parse_result: ParseResult, list: &mut List) -> ActionResult {
+    let (index, expression) = match parse_result.args().split_first() {
+        Some((index, rest)) if !rest.is_empty() => (*index, rest.join(" ")),
+        Some(..) => return ActionResult::InvalidArguments(ParseError::MissingArgument("date")),
+        None => return ActionResult::InvalidArguments(ParseError::MissingArgument("task")),
+    };
+
+    let index = match self::resolve_index(index, list) {
+        Ok(index) => index,
+        Err(result) => return result,
+    };
+
+    let due = match expression.as_str() {
+        C_NONE => None,
+        _ => match date::parse(&expression, date::today()) {
+            Some(due) => Some(due),
+            None => return ActionResult::InvalidArguments(ParseError::InvalidDate(expression)),
+        },
+    };
+
+    match list.set_due(index, due) {
+        Ok(()) => ActionResult::Ok,
+        Err(e) => map_list_error(e),
     }
+}
 
-    let index = self::extract_index_from_args(&parse_result.arguments);
+fn repeat(parse_result: ParseResult, list: &mut List) -> ActionResult {
+    let (index, rule) = match parse_result.args().as_slice() {
+        [index, rule] => (*index, rule.to_string()),
+        [] => return ActionResult::InvalidArguments(ParseError::MissingArgument("task")),
+        [_] => return ActionResult::InvalidArguments(ParseError::MissingArgument("recurrence")),
+        [_, _, extra, ..] => {
+            return ActionResult::InvalidArguments(ParseError::UnexpectedArgument(
+                extra.to_string(),
+            ))
+        }
+    };
+
+    let index = match self::resolve_index(index, list) {
+        Ok(index) => index,
+        Err(result) => return result,
+    };
+
+    let recurrence = match rule.as_str() {
+        C_NONE => None,
+        _ => match rule.parse::<Recurrence>() {
+            Ok(recurrence) => Some(recurrence),
+            Err(..) => return ActionResult::InvalidArguments(ParseError::InvalidRecurrence(rule)),
+        },
+    };
 
-    match list.mark_undone(index) {
+    match list.set_recurrence(index, recurrence) {
         Ok(()) => ActionResult::Ok,
         Err(e) => map_list_error(e),
     }
 }
 
+fn today(list: &mut List) -> ActionResult {
+    let today = date::today();
+    ActionResult::Feedback(list.to_view(&list.indices_due_today(today), today))
+}
+
+fn next(list: &mut List) -> ActionResult {
+    ActionResult::Feedback(list.to_view(&list.indices_actionable(), date::today()))
+}
+
+fn overdue(list: &mut List) -> ActionResult {
+    let today = date::today();
+    ActionResult::Feedback(list.to_view(&list.indices_overdue(today), today))
+}
+
 fn clear(list: &mut List, state: &mut State) -> ActionResult {
     if list.is_empty() {
         ActionResult::ListEmpty
@@ -198,9 +1028,25 @@ fn clear_confirm(raw_input: String, list: &mut List, state: &mut State) -> Actio
     }
 }
 
-fn save(list: &mut List, state: &mut State) -> ActionResult {
+fn undo(list: &mut List) -> ActionResult {
+    match list.undo() {
+        Ok(()) => ActionResult::Ok,
+        Err(e) => map_list_error(e),
+    }
+}
+
+fn redo(list: &mut List) -> ActionResult {
+    match list.redo() {
+        Ok(()) => ActionResult::Ok,
+        Err(e) => map_list_error(e),
+    }
+}
+
+fn save(parse_result: ParseResult, list: &mut List, state: &mut State) -> ActionResult {
     if list.is_empty() {
         ActionResult::ListEmpty
+    } else if !parse_result.rest().is_empty() {
+        self::save_text(parse_result.rest().to_string(), list, state)
     } else {
         state.set(C_SAVE, Status::NeedPlainText, None);
         ActionResult::NeedFilePath
@@ -210,13 +1056,15 @@ fn save(list: &mut List, state: &mut State) -> ActionResult {
 fn save_text(raw_input: String, list: &mut List, state: &mut State) -> ActionResult {
     state.reset();
 
-    match fs::write(raw_input, list.to_text().as_bytes()) {
-        Ok(..) => ActionResult::Ok,
-        Err(..) => ActionResult::CannotSave,
-    }
+    // Backups are only kept of the managed data files.
+    storage::save(&utils::expand_home(&raw_input), list, 0)
 }
 
-fn load(state: &mut State) -> ActionResult {
+fn load(parse_result: ParseResult, list: &mut List, state: &mut State) -> ActionResult {
+    if !parse_result.rest().is_empty() {
+        return self::load_text(parse_result.rest().to_string(), list, state);
+    }
+
     state.set(C_LOAD, Status::NeedPlainText, None);
     ActionResult::NeedFilePath
 }
@@ -224,8 +1072,12 @@ fn load(state: &mut State) -> ActionResult {
 fn load_text(raw_input: String, list: &mut List, state: &mut State) -> ActionResult {
     state.reset();
 
+    let raw_input = utils::expand_home(&raw_input);
+
+    let format = Format::from_path(&raw_input);
+
     match fs::read_to_string(raw_input) {
-        Result::Ok(contents) => match list.from_text(&contents) {
+        Result::Ok(contents) => match list.deserialize(&contents, format) {
             Ok(..) => ActionResult::Ok,
             Err(e) => map_list_error(e),
         },
@@ -233,52 +1085,313 @@ fn load_text(raw_input: String, list: &mut List, state: &mut State) -> ActionRes
     }
 }
 
-// =========== Process Action ===========
+fn backups(parse_result: ParseResult, list: &mut List, state: &mut State) -> ActionResult {
+    let backups = match state.list_path() {
+        Some(path) => storage::backups(&path),
+        None => Vec::new(),
+    };
 
-pub fn process(input: String, list: &mut List, state: &mut State) -> ActionResult {
-    if state.status.is_some() {
-        let raw_input = utils::trim_str(&input);
+    match parse_result.args().as_slice() {
+        [] => {
+            let mut result = String::new();
 
-        match state.status {
-            Some(Status::NeedPlainText) => match state.command {
-                Some(C_ADD) => self::add_text(raw_input, list, state),
-                Some(C_EDIT) => self::edit_text(raw_input, list, state),
-                Some(C_SAVE) => self::save_text(raw_input, list, state),
-                Some(C_LOAD) => self::load_text(raw_input, list, state),
-                _ => ActionResult::Sh,
-            },
-            Some(Status::NeedConfirmation) => match state.command {
-                Some(C_REMOVE) => self::remove_confirm(raw_input, list, state),
-                Some(C_CLEAR) => self::clear_confirm(raw_input, list, state),
-                _ => ActionResult::Sh,
-            },
-            None => ActionResult::Sh,
-        }
-    } else {
-        let parse_result = command_parser::parse(&input);
+            backups.iter().enumerate().for_each(|(i, b)| {
+                result.push_str(&format!("{}) {b}\n", i + 1));
+            });
 
-        match parse_result.command {
-            C_EXIT => self::exit(),
-            C_HELP => self::help(),
-            C_LIST => self::list(list),
-            C_ADD => self::add(state),
-            C_EDIT => self::edit(parse_result, list, state),
-            C_REMOVE => self::remove(parse_result, list, state),
-            C_DONE => self::done(parse_result, list),
-            C_UNDONE => self::undone(parse_result, list),
-            C_CLEAR => self::clear(list, state),
-            C_SAVE => self::save(list, state),
-            C_LOAD => self::load(state),
-            _ => ActionResult::UnknownCommand,
+            ActionResult::Feedback(result)
         }
-    }
+        [C_RESTORE, index] => {
+            let backup = match command_parser::parse_position(index) {
+                Ok(index) => backups.get(index - 1),
+                Err(e) => return ActionResult::InvalidArguments(e),
+            };
+
+            let (backup, path) = match (backup, state.list_path()) {
+                (Some(backup), Some(path)) => (backup, path),
+                _ => return ActionResult::BackupNotFound,
+            };
+
+            match fs::read_to_string(backup) {
+                Ok(contents) => match list.deserialize(&contents, Format::from_path(&path)) {
+                    // The list is readable again, so it may replace a file
+                    // that was not.
+                    Ok(..) => {
+                        state.autosave = true;
+                        ActionResult::Ok
+                    }
+                    Err(e) => map_list_error(e),
+                },
+                Err(..) => ActionResult::CannotLoad,
+            }
+        }
+        [C_RESTORE] => ActionResult::InvalidArguments(ParseError::MissingArgument("backup")),
+        [C_RESTORE, _, extra, ..] | [extra, ..] => {
+            ActionResult::InvalidArguments(ParseError::UnexpectedArgument(extra.to_string()))
+        }
+    }
+}
+
+// The key and file of the existing list called `name`, where the default
+// list has no key.
+pub fn existing_list(name: &str, state: &State) -> Result<(Option<String>, String), ActionResult> {
+    if !storage::is_list_name(name) {
+        let e = ParseError::InvalidListName(name.to_string());
+        return Err(ActionResult::InvalidArguments(e));
+    }
+
+    let Some(data_path) = &state.data_path else {
+        return Err(ActionResult::ListNotFound);
+    };
+
+    let key = (name != DEFAULT_LIST_NAME).then(|| name.to_string());
+    let path = storage::list_path(data_path, key.as_deref());
+
+    match key.is_none() || Path::new(&path).exists() {
+        true => Ok((key, path)),
+        false => Err(ActionResult::ListNotFound),
+    }
+}
+
+fn lists(state: &State) -> ActionResult {
+    let mut names = vec![DEFAULT_LIST_NAME.to_string()];
+
+    if let Some(path) = &state.data_path {
+        names.extend(storage::lists(path));
+    }
+
+    let mut result = String::new();
+
+    names.iter().for_each(|name| {
+        let mark = if name == state.list_name() { "*" } else { " " };
+        result.push_str(&format!("{mark} {name}\n"));
+    });
+
+    ActionResult::Feedback(result)
+}
+
+// `list create NAME` adds an empty list, `list delete NAME` deletes one
+// that is not in use.
+fn change_lists(action: &str, args: &[&str], list: &mut List, state: &mut State) -> ActionResult {
+    let invalid = ActionResult::InvalidArguments;
+
+    let name = match args {
+        [] => return invalid(ParseError::MissingArgument("list name")),
+        [name] => *name,
+        [_, extra, ..] => return invalid(ParseError::UnexpectedArgument(extra.to_string())),
+    };
+
+    match action {
+        C_CREATE => {
+            if !storage::is_list_name(name) {
+                return invalid(ParseError::InvalidListName(name.to_string()));
+            }
+
+            let Some(data_path) = &state.data_path else {
+                return ActionResult::CannotSave;
+            };
+
+            let path = storage::list_path(data_path, Some(name));
+
+            if name == DEFAULT_LIST_NAME || Path::new(&path).exists() {
+                return ActionResult::ListExists;
+            }
+
+            storage::save(&path, &list.blank(), 0)
+        }
+        _ => {
+            let (key, path) = match self::existing_list(name, state) {
+                Ok(found) => found,
+                Err(result) => return result,
+            };
+
+            if key.is_none() || key == state.list {
+                return ActionResult::ListInUse;
+            }
+
+            storage::delete(&path, state.backups)
+        }
+    }
+}
+
+// `use NAME` saves the list in use and switches to another one. A list
+// that cannot be read is not switched to.
+fn use_list(parse_result: ParseResult, list: &mut List, state: &mut State) -> ActionResult {
+    let name = match parse_result.args().as_slice() {
+        [] => return ActionResult::InvalidArguments(ParseError::MissingArgument("list name")),
+        [name] => *name,
+        [_, extra, ..] => {
+            return ActionResult::InvalidArguments(ParseError::UnexpectedArgument(
+                extra.to_string(),
+            ))
+        }
+    };
+
+    let (key, path) = match self::existing_list(name, state) {
+        Ok(found) => found,
+        Err(result) => return result,
+    };
+
+    if key == state.list {
+        return ActionResult::Sh;
+    }
+
+    let mut next = list.blank();
+    let loaded = storage::load(&path, &mut next);
+
+    if loaded != ActionResult::Ok {
+        return loaded;
+    }
+
+    if let (true, Some(current)) = (state.autosave, state.list_path()) {
+        let saved = storage::save(&current, list, state.backups);
+
+        if saved != ActionResult::Ok {
+            return saved;
+        }
+    }
+
+    next.clear_history();
+    *list = next;
+    state.list = key;
+    state.autosave = true;
+
+    // The list it left was saved above, the one it loaded is unchanged.
+    ActionResult::Sh
+}
+
+// `move 3 to groceries` hands task 3 and its subtasks over to another
+// list. The other list is saved first, so a failure never loses a task.
+fn move_task(parse_result: ParseResult, list: &mut List, state: &mut State) -> ActionResult {
+    let invalid = ActionResult::InvalidArguments;
+
+    let (task, name) = match parse_result.args().as_slice() {
+        [] => return invalid(ParseError::MissingArgument("task")),
+        [_] | [_, C_TO] => return invalid(ParseError::MissingArgument("list name")),
+        [task, C_TO, name] => (*task, *name),
+        [_, C_TO, _, extra, ..] | [_, extra, ..] => {
+            return invalid(ParseError::UnexpectedArgument(extra.to_string()))
+        }
+    };
+
+    let index = match self::resolve_index(task, list) {
+        Ok(index) => index,
+        Err(result) => return result,
+    };
+
+    let (key, path) = match self::existing_list(name, state) {
+        Ok(found) => found,
+        Err(result) => return result,
+    };
+
+    if key == state.list {
+        return ActionResult::ListInUse;
+    }
+
+    let mut target = list.blank();
+    let loaded = storage::load(&path, &mut target);
+
+    if loaded != ActionResult::Ok {
+        return loaded;
+    }
+
+    let adopted = list
+        .copy_subtree(index)
+        .and_then(|tasks| target.adopt(tasks));
+
+    if let Err(e) = adopted {
+        return map_list_error(e);
+    }
+
+    let saved = storage::save(&path, &target, state.backups);
+
+    if saved != ActionResult::Ok {
+        return saved;
+    }
+
+    match list.release(index) {
+        Ok(()) => ActionResult::Ok,
+        Err(e) => map_list_error(e),
+    }
+}
+
+// =========== Process Action ===========
+
+pub fn process(input: String, list: &mut List, state: &mut State) -> ActionResult {
+    if state.status.is_some() {
+        let raw_input = input.trim().to_string();
+
+        match state.status {
+            Some(Status::NeedPlainText) => match state.command {
+                Some(C_ADD) => self::add_text(raw_input, list, state),
+                Some(C_EDIT) => self::edit_text(raw_input, list, state),
+                Some(C_SAVE) => self::save_text(raw_input, list, state),
+                Some(C_LOAD) => self::load_text(raw_input, list, state),
+                _ => ActionResult::Sh,
+            },
+            Some(Status::NeedConfirmation) => match state.command {
+                Some(C_REMOVE) => self::remove_confirm(raw_input, list, state),
+                Some(C_DONE) => self::done_confirm(raw_input, list, state),
+                Some(C_CLEAR) => self::clear_confirm(raw_input, list, state),
+                _ => ActionResult::Sh,
+            },
+            None => ActionResult::Sh,
+        }
+    } else {
+        let parse_result = match command_parser::parse(&input) {
+            Ok(parse_result) => parse_result,
+            Err(e) => return ActionResult::InvalidInput(e),
+        };
+
+        match parse_result.command.as_str() {
+            C_EXIT => self::exit(),
+            C_HELP => self::help(),
+            C_LIST => self::list(parse_result, list, state),
+            C_LISTS => self::lists(state),
+            C_USE => self::use_list(parse_result, list, state),
+            C_MOVE => self::move_task(parse_result, list, state),
+            C_FIND => self::find(parse_result, list, state),
+            C_VIEW => self::view(parse_result, list, state),
+            C_BLOCK => self::block(parse_result, list),
+            C_UNBLOCK => self::unblock(parse_result, list),
+            C_NEXT => self::next(list),
+            C_ARCHIVE => self::archive(parse_result, list, state),
+            C_UNARCHIVE => self::unarchive(parse_result, list),
+            C_TRASH => self::trash(parse_result, list),
+            C_RESTORE => self::restore(parse_result.args().as_slice(), list),
+            C_REPEAT => self::repeat(parse_result, list),
+            C_ADD => self::add(parse_result, list, state),
+            C_EDIT => self::edit(parse_result, list, state),
+            C_REMOVE => self::remove(parse_result, list, state),
+            C_DONE => self::done(parse_result, list, state),
+            C_UNDONE => self::undone(parse_result, list),
+            C_START => self::set_status(parse_result, list, TaskStatus::Started),
+            C_WAIT => self::set_status(parse_result, list, TaskStatus::Waiting),
+            C_CANCEL => self::set_status(parse_result, list, TaskStatus::Cancelled),
+            C_REOPEN => self::set_status(parse_result, list, TaskStatus::Todo),
+            C_PRIO => self::prio(parse_result, list),
+            C_DUE => self::due(parse_result, list),
+            C_TODAY => self::today(list),
+            C_OVERDUE => self::overdue(list),
+            C_PROJECTS => self::projects(list),
+            C_CONTEXTS => self::contexts(list),
+            C_CLEAR => self::clear(list, state),
+            C_UNDO => self::undo(list),
+            C_REDO => self::redo(list),
+            C_BACKUPS => self::backups(parse_result, list, state),
+            C_SAVE => self::save(parse_result, list, state),
+            C_LOAD => self::load(parse_result, list, state),
+            _ => ActionResult::UnknownCommand,
+        }
+    }
 }
 
 // =========== Tests ===========
 
 #[cfg(test)]
 mod tests {
-    use crate::config::{C_Y, C_YES};
+    use crate::config::{Config, C_Y, C_YES};
+    use std::collections::HashSet;
 
     use super::*;
 
@@ -301,14 +1414,49 @@ mod tests {
             "{C_EXIT}     - Exit program
 {C_HELP}     - View available commands
 {C_LIST}     - View all tasks
+{C_LIST} {C_PRIO} - View all tasks sorted by priority
+{C_LIST} +work - View tasks of a +project or @context
+{C_LIST} {O_PENDING} {O_SORT} due {O_LIMIT} 5 - Filter ({O_PENDING}, {O_DONE}) and sort (text, created, due, priority; {O_REVERSE})
+{C_LIST} {O_LONG} - View tasks with when they were created and completed
+{C_FIND} milk - Find tasks containing text, or matching a regex with {C_FIND} '/^buy\\s/'
 {C_CLEAR}    - Clear tasks
-{C_ADD}      - Add new task
-{C_EDIT} 2   - Edit task by index where 2 is index
+{C_ARCHIVE}  - Move done tasks to the archive (or {C_ARCHIVE} {O_OLDER} 30 for those done over 30 days ago)
+{C_ARCHIVE} {C_LIST} - View archived tasks ({C_ARCHIVE} {C_SEARCH} milk finds some)
+{C_UNARCHIVE} 2 - Move archived task back to the list where 2 is its index in the archive
+{C_UNDO}     - Undo last change
+{C_REDO}     - Redo last undone change
+{C_ADD}      - Add new task (or {C_ADD} buy milk)
+{C_ADD} {O_UNDER} 3 - Add a subtask to task 3 (or {C_ADD} {O_UNDER} 3 buy milk)
+{C_EDIT} 2   - Edit task by index where 2 is index (or {C_EDIT} 2 new text)
 {C_REMOVE} 2 - Delete task by index where 2 is index
+{C_TRASH}    - View removed tasks ({C_TRASH} {C_EMPTY} purges them)
+{C_RESTORE} 2 - Put removed task back where 2 is its index in the trash
 {C_DONE} 2   - Mark task as DONE where 2 is index
 {C_UNDONE} 2 - Mark task as UNDONE where 2 is index
-{C_SAVE}     - Save list to file
-{C_LOAD}     - Load list from file"
+{C_START} 2  - Mark task as started (also {C_WAIT}, {C_CANCEL} and {C_REOPEN})
+{C_BLOCK} 5 {C_ON} 3 - Make task 5 wait for task 3 (undo with {C_UNBLOCK} 5 {C_ON} 3, or {C_UNBLOCK} 5 for all)
+{C_NEXT}     - View tasks that can be worked on now
+{C_PRIO} 2 high - Set priority (none/low/medium/high) where 2 is index
+{C_DUE} 2 fri - Set due date (2026-11-01, tomorrow, next fri, +3d, none) where 2 is index
+{C_REPEAT} 2 weekly:mon - Repeat task when done (daily, weekly, weekly:mon,thu, monthly:15, after:3d, none)
+{C_TODAY}    - View tasks due today
+{C_OVERDUE}  - View overdue tasks
+{C_PROJECTS} - View all +projects with task counts
+{C_CONTEXTS} - View all @contexts with task counts
+{C_SAVE}     - Save list to file (or {C_SAVE} ~/todo.txt)
+{C_LOAD}     - Load list from file (or {C_LOAD} ~/todo.txt)
+{C_VIEW}     - View saved views ({C_VIEW} weekly shows one)
+{C_VIEW} {C_ADD} weekly \"status:pending and (tag:work or prio>=high) and due<+7d\" - Save a view
+{C_VIEW} {C_REMOVE} weekly - Delete a view
+{C_LISTS}    - View lists, the one in use marked with *
+{C_USE} work - Switch to another list (or {C_USE} {DEFAULT_LIST_NAME})
+{C_LIST} {C_CREATE} groceries - Create a list ({C_LIST} {C_DELETE} groceries deletes one)
+{C_MOVE} 3 {C_TO} groceries - Move task and its subtasks to another list
+{C_BACKUPS}  - View backups of the list in use
+{C_BACKUPS} {C_RESTORE} 2 - Restore backup where 2 is backup index
+
+Tasks are addressed by index (2), subtask path (3.2) or by id (#7)
+{C_REMOVE}, {C_DONE}, {C_UNDONE}, {C_START}, {C_WAIT}, {C_CANCEL} and {C_REOPEN} take several (1 3, 2-6, 4,7-9 or {C_ALL})"
         );
 
         let result = process(C_HELP.to_string(), &mut list, &mut state);
@@ -334,7 +1482,7 @@ mod tests {
         let _ = list.add("test1".to_string());
         let _ = list.add("test2".to_string());
 
-        let expected = "1) [ ] test1;\n2) [ ] test2;\n".to_string();
+        let expected = "1) #1 [ ] test1;\n2) #2 [ ] test2;\n".to_string();
         let result = process(C_LIST.to_string(), &mut list, &mut state);
 
         assert_eq!(result, ActionResult::Feedback(expected));
@@ -366,182 +1514,1449 @@ mod tests {
     }
 
     #[test]
-    fn test_process_edit() {
+    fn test_process_edit() {
+        let mut list = List::new();
+        let mut state = State::new();
+
+        let task_before_edit = "test";
+        let task_after_edit = "new-test";
+
+        let _ = list.add(task_before_edit.to_string());
+
+        assert!(list.get(0).unwrap().text == task_before_edit);
+        let result = process(format!("{C_EDIT} 1"), &mut list, &mut state);
+
+        assert_eq!(result, ActionResult::NeedTask);
+        assert_eq!(state.status, Some(Status::NeedPlainText));
+        assert_eq!(state.command, Some(C_EDIT));
+        assert_eq!(state.task_index, Some(0));
+
+        let result = process(task_after_edit.to_string(), &mut list, &mut state);
+
+        assert_eq!(result, ActionResult::Ok);
+        assert!(list.get(0).unwrap().text == task_after_edit);
+        assert!(state.status.is_none());
+        assert!(state.command.is_none());
+        assert!(state.task_index.is_none());
+    }
+
+    #[test]
+    fn test_process_find() {
+        let mut list = List::new();
+        let mut state = State::new().with_highlight(true);
+
+        let _ = list.add("Buy milk".to_string());
+        let _ = list.add("call mum".to_string());
+        let _ = list.add("buy bread".to_string());
+
+        let result = process(format!("{C_FIND} BUY"), &mut list, &mut state);
+        assert_eq!(
+            result,
+            ActionResult::Feedback(
+                "1) #1 [ ] \x1b[1;4mBuy\x1b[0m milk;\n3) #3 [ ] \x1b[1;4mbuy\x1b[0m bread;\n"
+                    .to_string()
+            )
+        );
+
+        let result = process(format!("{C_FIND} '/^b.y\\b/'"), &mut list, &mut state);
+        assert_eq!(
+            result,
+            ActionResult::Feedback("3) #3 [ ] \x1b[1;4mbuy\x1b[0m bread;\n".to_string())
+        );
+
+        let result = process(format!("{C_FIND} a.l"), &mut list, &mut state);
+        assert_eq!(result, ActionResult::Feedback(String::new()));
+
+        let result = process(format!("{C_FIND} /a.l/"), &mut list, &mut state);
+        assert_eq!(
+            result,
+            ActionResult::Feedback("2) #2 [ ] c\x1b[1;4mall\x1b[0m mum;\n".to_string())
+        );
+
+        state.highlight = false;
+
+        let result = process(format!("{C_FIND} /a.l/"), &mut list, &mut state);
+        assert_eq!(
+            result,
+            ActionResult::Feedback("2) #2 [ ] call mum;\n".to_string())
+        );
+    }
+
+    #[test]
+    fn test_process_find_invalid() {
+        let mut list = List::new();
+        let mut state = State::new();
+
+        let result = process(C_FIND.to_string(), &mut list, &mut state);
+        assert_eq!(
+            result,
+            ActionResult::InvalidArguments(ParseError::MissingArgument("query"))
+        );
+
+        let result = process(format!("{C_FIND} /(/"), &mut list, &mut state);
+        assert_eq!(
+            result,
+            ActionResult::InvalidArguments(ParseError::InvalidRegex("/(/".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_process_view() {
+        let path = std::env::temp_dir().join("todocli_test_process_view.conf");
+        let path = path.to_string_lossy().to_string();
+        let _ = fs::remove_file(&path);
+
+        let mut list = List::new();
+        let mut state = State::new().with_views(&Config::load(&path));
+
+        let _ = list.add("report +work".to_string());
+        let _ = list.add("milk @home".to_string());
+        let _ = list.add("slides +work".to_string());
+        let _ = list.mark_done(&[2]);
+
+        let result = process(
+            format!("{C_VIEW} {C_ADD} open 'status:pending and (tag:work or text:milk)'"),
+            &mut list,
+            &mut state,
+        );
+        assert_eq!(result, ActionResult::Ok);
+
+        let result = process(
+            format!("{C_VIEW} {C_ADD} done status:done"),
+            &mut list,
+            &mut state,
+        );
+        assert_eq!(result, ActionResult::Ok);
+
+        let result = process(C_VIEW.to_string(), &mut list, &mut state);
+        assert_eq!(
+            result,
+            ActionResult::Feedback(
+                "open: status:pending and (tag:work or text:milk)\ndone: status:done\n".to_string()
+            )
+        );
+
+        let result = process(format!("{C_VIEW} open"), &mut list, &mut state);
+        assert_eq!(
+            result,
+            ActionResult::Feedback("1) #1 [ ] report +work;\n2) #2 [ ] milk @home;\n".to_string())
+        );
+
+        let result = process(format!("{C_VIEW} {C_REMOVE} open"), &mut list, &mut state);
+        assert_eq!(result, ActionResult::Ok);
+
+        let config = Config::load(&path);
+        assert_eq!(
+            config.views,
+            vec![("done".to_string(), "status:done".to_string())]
+        );
+
+        let result = process(format!("{C_VIEW} open"), &mut list, &mut state);
+        assert_eq!(result, ActionResult::ViewNotFound);
+
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_process_view_invalid() {
+        let mut list = List::new();
+        let mut state = State::new();
+
+        let result = process(format!("{C_VIEW} {C_ADD} weekly"), &mut list, &mut state);
+        assert_eq!(
+            result,
+            ActionResult::InvalidArguments(ParseError::MissingArgument("query"))
+        );
+
+        let result = process(format!("{C_VIEW} {C_ADD} a.b tag:x"), &mut list, &mut state);
+        assert_eq!(
+            result,
+            ActionResult::InvalidArguments(ParseError::InvalidViewName("a.b".to_string()))
+        );
+
+        let result = process(
+            format!("{C_VIEW} {C_ADD} w 'tag:x and'"),
+            &mut list,
+            &mut state,
+        );
+        assert_eq!(
+            result,
+            ActionResult::InvalidArguments(ParseError::InvalidQuery(query::Error::UnexpectedEnd))
+        );
+
+        let result = process(
+            format!("{C_VIEW} {C_ADD} w 'tag:x\nview.y = tag:y'"),
+            &mut list,
+            &mut state,
+        );
+        assert_eq!(
+            result,
+            ActionResult::InvalidArguments(ParseError::InvalidQuery(
+                query::Error::UnexpectedToken("\n".to_string())
+            ))
+        );
+
+        let result = process(format!("{C_VIEW} {C_ADD} w tag:x"), &mut list, &mut state);
+        assert_eq!(result, ActionResult::CannotSave);
+
+        let result = process(format!("{C_VIEW} {C_REMOVE} w"), &mut list, &mut state);
+        assert_eq!(result, ActionResult::ViewNotFound);
+    }
+
+    #[test]
+    fn test_process_add_inline() {
+        let mut list = List::new();
+        let mut state = State::new();
+
+        let result = process(format!("{C_ADD} buy  milk\n"), &mut list, &mut state);
+
+        assert_eq!(result, ActionResult::Ok);
+        assert!(list.get(0).unwrap().text == "buy  milk");
+        assert!(state.status.is_none());
+    }
+
+    #[test]
+    fn test_process_add_quoted() {
+        let mut list = List::new();
+        let mut state = State::new();
+
+        let result = process(
+            format!("{C_ADD} \"buy  milk\"\t'at\tnoon' -- --fast\n"),
+            &mut list,
+            &mut state,
+        );
+
+        assert_eq!(result, ActionResult::Ok);
+        assert!(list.get(0).unwrap().text == "buy  milk\tat\tnoon --fast");
+
+        let result = process(format!("{C_ADD} \"buy milk"), &mut list, &mut state);
+
+        assert_eq!(
+            result,
+            ActionResult::InvalidInput(ParseError::UnterminatedQuote('"'))
+        );
+        assert!(result.message().unwrap() == "unterminated \" quote");
+        assert!(state.status.is_none());
+    }
+
+    #[test]
+    fn test_process_add_prompt_spacing() {
+        let mut list = List::new();
+        let mut state = State::new();
+
+        let _ = process(C_ADD.to_string(), &mut list, &mut state);
+        let result = process("  buy  \"oat\"  milk \n".to_string(), &mut list, &mut state);
+
+        assert_eq!(result, ActionResult::Ok);
+        assert!(list.get(0).unwrap().text == "buy  \"oat\"  milk");
+    }
+
+    #[test]
+    fn test_process_edit_inline() {
+        let mut list = List::new();
+        let mut state = State::new();
+
+        let _ = list.add("test".to_string());
+
+        let result = process(format!("{C_EDIT} 1 new  text"), &mut list, &mut state);
+
+        assert_eq!(result, ActionResult::Ok);
+        assert!(list.get(0).unwrap().text == "new  text");
+        assert!(state.status.is_none());
+
+        let result = process(format!("{C_EDIT} 2 new text"), &mut list, &mut state);
+        assert_eq!(result, ActionResult::TaskNotFound);
+    }
+
+    #[test]
+    fn test_process_save_load_inline() {
+        let mut list = List::new();
+        let mut state = State::new();
+        let path = std::env::temp_dir().join("todocli_test_process_inline.txt");
+        let path = path.to_string_lossy().to_string();
+
+        let _ = list.add("test".to_string());
+
+        let result = process(format!("{C_SAVE} {path}"), &mut list, &mut state);
+        assert_eq!(result, ActionResult::Ok);
+        assert!(state.status.is_none());
+
+        let mut list = List::new();
+
+        let result = process(format!("{C_LOAD} {path}"), &mut list, &mut state);
+        assert_eq!(result, ActionResult::Ok);
+        assert!(state.status.is_none());
+        assert!(list.get(0).unwrap().text == "test");
+
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_process_edit_not_found() {
+        let mut list = List::new();
+        let mut state = State::new();
+
+        let result = process(format!("{C_EDIT} 1"), &mut list, &mut state);
+
+        assert_eq!(result, ActionResult::TaskNotFound);
+        assert!(state.status.is_none());
+        assert!(state.command.is_none());
+        assert!(state.task_index.is_none());
+    }
+
+    #[test]
+    fn test_process_index_edge_cases() {
+        let mut list = List::new();
+        let mut state = State::new();
+
+        for _ in 0..10 {
+            let _ = list.add("test".to_string());
+        }
+
+        let cases = [
+            (C_EDIT.to_string(), ParseError::MissingArgument("task")),
+            (format!("{C_EDIT} 0"), ParseError::ZeroIndex),
+            (format!("{C_DONE} 0"), ParseError::ZeroIndex),
+            (
+                format!("{C_DONE} a1"),
+                ParseError::NotANumber("a1".to_string()),
+            ),
+            (
+                format!("{C_DONE} 10x"),
+                ParseError::NotANumber("10x".to_string()),
+            ),
+            (
+                format!("{C_DONE} 1.x"),
+                ParseError::NotANumber("x".to_string()),
+            ),
+            (
+                format!("{C_EDIT} 99999999999999999999999"),
+                ParseError::NumberTooLarge("99999999999999999999999".to_string()),
+            ),
+        ];
+
+        for (input, reason) in cases {
+            let result = process(input, &mut list, &mut state);
+            assert_eq!(result, ActionResult::InvalidArguments(reason));
+            assert!(state.status.is_none());
+        }
+
+        let result = process(format!("{C_DONE} 10"), &mut list, &mut state);
+        assert_eq!(result, ActionResult::Ok);
+        assert!(list.get(9).unwrap().is_done());
+    }
+
+    #[test]
+    fn test_process_edit_invalid() {
+        let mut list = List::new();
+        let mut state = State::new();
+
+        let result = process(format!("{C_EDIT} test"), &mut list, &mut state);
+
+        assert_eq!(
+            result,
+            ActionResult::InvalidArguments(ParseError::NotANumber("test".to_string()))
+        );
+        assert!(state.status.is_none());
+        assert!(state.command.is_none());
+        assert!(state.task_index.is_none());
+    }
+
+    #[test]
+    fn test_process_remove() {
+        let mut list = List::new();
+        let mut state = State::new();
+
+        let _ = list.add("test".to_string());
+
+        assert!(!list.is_empty());
+        let result = process(format!("{C_REMOVE} 1"), &mut list, &mut state);
+
+        assert_eq!(result, ActionResult::NeedConfirm);
+        assert_eq!(state.command, Some(C_REMOVE));
+        assert_eq!(state.status, Some(Status::NeedConfirmation));
+        assert_eq!(state.task_index, Some(0));
+
+        let result = process(C_YES.to_string(), &mut list, &mut state);
+
+        assert_eq!(result, ActionResult::Ok);
+        assert!(list.is_empty());
+        assert!(state.status.is_none());
+        assert!(state.command.is_none());
+        assert!(state.task_index.is_none());
+    }
+
+    #[test]
+    fn test_process_remove_several() {
+        let mut list = List::new();
+        let mut state = State::new();
+
+        for text in ["a", "b", "c", "d", "e"] {
+            let _ = list.add(text.to_string());
+        }
+
+        let result = process(format!("{C_REMOVE} 4,1-2"), &mut list, &mut state);
+
+        assert_eq!(
+            result,
+            ActionResult::NeedConfirmTasks(
+                "4) #4 [ ] d;\n1) #1 [ ] a;\n2) #2 [ ] b;\n".to_string()
+            )
+        );
+        assert!(result.message().unwrap().ends_with("b;\nconfirm? (y/n)"));
+        assert_eq!(state.status, Some(Status::NeedConfirmation));
+        assert_eq!(state.task_indices, [3, 0, 1]);
+
+        let result = process(C_YES.to_string(), &mut list, &mut state);
+
+        assert_eq!(result, ActionResult::Ok);
+        assert!(list.get(0).unwrap().text == "c");
+        assert!(list.get(1).unwrap().text == "e");
+        assert!(list.get(2).is_err());
+        assert!(state.task_indices.is_empty());
+
+        let _ = process(C_UNDO.to_string(), &mut list, &mut state);
+        assert!(list.get(4).unwrap().text == "e");
+    }
+
+    #[test]
+    fn test_process_remove_several_decline() {
+        let mut list = List::new();
+        let mut state = State::new();
+
+        for text in ["a", "b", "c"] {
+            let _ = list.add(text.to_string());
+        }
+
+        let _ = process(format!("{C_REMOVE} {C_ALL}"), &mut list, &mut state);
+        let result = process("n".to_string(), &mut list, &mut state);
+
+        assert_eq!(result, ActionResult::Sh);
+        assert!(list.get(2).is_ok());
+        assert!(state.task_indices.is_empty());
+    }
+
+    #[test]
+    fn test_process_remove_decline() {
+        let mut list = List::new();
+        let mut state = State::new();
+        let task = "test";
+
+        let _ = list.add(task.to_string());
+
+        assert!(list.get(0).unwrap().text == task);
+        let result = process(format!("{C_REMOVE} 1"), &mut list, &mut state);
+
+        assert_eq!(result, ActionResult::NeedConfirm);
+        assert_eq!(state.command, Some(C_REMOVE));
+        assert_eq!(state.status, Some(Status::NeedConfirmation));
+        assert_eq!(state.task_index, Some(0));
+
+        let result = process("n".to_string(), &mut list, &mut state);
+
+        assert_eq!(result, ActionResult::Sh);
+        assert!(list.get(0).unwrap().text == task);
+        assert!(state.status.is_none());
+        assert!(state.command.is_none());
+        assert!(state.task_index.is_none());
+    }
+
+    #[test]
+    fn test_process_remove_not_found() {
+        let mut list = List::new();
+        let mut state = State::new();
+
+        let result = process(format!("{C_REMOVE} 1"), &mut list, &mut state);
+
+        assert_eq!(result, ActionResult::TaskNotFound);
+        assert!(state.status.is_none());
+        assert!(state.command.is_none());
+        assert!(state.task_index.is_none());
+    }
+
+    #[test]
+    fn test_process_remove_invalid() {
+        let mut list = List::new();
+        let mut state = State::new();
+
+        let result = process(format!("{C_REMOVE} 1 two"), &mut list, &mut state);
+
+        assert_eq!(
+            result,
+            ActionResult::InvalidArguments(ParseError::NotANumber("two".to_string()))
+        );
+        assert!(state.status.is_none());
+        assert!(state.command.is_none());
+        assert!(state.task_index.is_none());
+    }
+
+    #[test]
+    fn test_process_done() {
+        let mut list = List::new();
+        let mut state = State::new();
+
+        let _ = list.add("test".to_string());
+
+        assert!(!list.get(0).unwrap().is_done());
+
+        let result = process(format!("{C_DONE} 1"), &mut list, &mut state);
+
+        assert!(list.get(0).unwrap().is_done());
+        assert_eq!(result, ActionResult::Ok);
+    }
+
+    #[test]
+    fn test_process_done_several() {
+        let mut list = List::new();
+        let mut state = State::new();
+
+        for text in ["a", "b", "c", "d", "e", "f"] {
+            let _ = list.add(text.to_string());
+        }
+
+        let result = process(format!("{C_DONE} 1 #3 5,2-2"), &mut list, &mut state);
+        assert_eq!(result, ActionResult::Ok);
+
+        let done: Vec<bool> = (0..6).map(|i| list.get(i).unwrap().is_done()).collect();
+        assert_eq!(done, [true, true, true, false, true, false]);
+
+        let result = process(format!("{C_UNDONE} 2-5"), &mut list, &mut state);
+        assert_eq!(result, ActionResult::Ok);
+
+        let done: Vec<bool> = (0..6).map(|i| list.get(i).unwrap().is_done()).collect();
+        assert_eq!(done, [true, false, false, false, false, false]);
+
+        let result = process(format!("{C_DONE} {C_ALL}"), &mut list, &mut state);
+        assert_eq!(result, ActionResult::Ok);
+        assert!((0..6).all(|i| list.get(i).unwrap().is_done()));
+
+        let _ = process(C_UNDO.to_string(), &mut list, &mut state);
+        assert!(!list.get(1).unwrap().is_done());
+        assert!(list.get(0).unwrap().is_done());
+    }
+
+    #[test]
+    fn test_process_done_several_invalid() {
+        let mut list = List::new();
+        let mut state = State::new();
+
+        let result = process(format!("{C_DONE} {C_ALL}"), &mut list, &mut state);
+        assert_eq!(result, ActionResult::ListEmpty);
+
+        for text in ["a", "b", "c"] {
+            let _ = list.add(text.to_string());
+        }
+
+        let result = process(format!("{C_DONE} 2-4"), &mut list, &mut state);
+        assert_eq!(result, ActionResult::TaskNotFound);
+
+        let result = process(format!("{C_DONE} 1 4"), &mut list, &mut state);
+        assert_eq!(result, ActionResult::TaskNotFound);
+
+        let result = process(format!("{C_DONE} 3-1"), &mut list, &mut state);
+        assert_eq!(
+            result,
+            ActionResult::InvalidArguments(ParseError::InvalidRange("3-1".to_string()))
+        );
+
+        assert!((0..3).all(|i| !list.get(i).unwrap().is_done()));
+    }
+
+    // a (with a1 and a2) and b, all added through `add --under`.
+    fn tree(list: &mut List, state: &mut State) {
+        for input in ["a", "b", "--under 1 a1", "--under 1 a2"] {
+            let result = process(format!("{C_ADD} {input}"), list, state);
+            assert_eq!(result, ActionResult::Ok);
+        }
+    }
+
+    #[test]
+    fn test_process_add_subtask() {
+        let mut list = List::new();
+        let mut state = State::new();
+        tree(&mut list, &mut state);
+
+        let result = process(format!("{C_ADD} {O_UNDER} 1.2"), &mut list, &mut state);
+        assert_eq!(result, ActionResult::NeedTask);
+
+        let result = process("a2x".to_string(), &mut list, &mut state);
+        assert_eq!(result, ActionResult::Ok);
+        assert_eq!(
+            process(C_LIST.to_string(), &mut list, &mut state),
+            ActionResult::Feedback(
+                "1) #1 [ ] a;\n  1.1) #3 [ ] a1;\n  1.2) #4 [ ] a2;\n    1.2.1) #5 [ ] a2x;\n2) #2 [ ] b;\n"
+                    .to_string()
+            )
+        );
+
+        assert_eq!(
+            process(format!("{C_ADD} {O_UNDER}"), &mut list, &mut state),
+            ActionResult::InvalidArguments(ParseError::MissingArgument("task"))
+        );
+        assert_eq!(
+            process(format!("{C_ADD} {O_UNDER} 1.9 x"), &mut list, &mut state),
+            ActionResult::TaskNotFound
+        );
+        assert_eq!(
+            process(format!("{C_ADD} -- {O_UNDER} 1"), &mut list, &mut state),
+            ActionResult::Ok
+        );
+        assert!(list.get(5).unwrap().text == "--under 1");
+    }
+
+    #[test]
+    fn test_process_done_subtask() {
+        let mut list = List::new();
+        let mut state = State::new();
+        tree(&mut list, &mut state);
+
+        let result = process(format!("{C_DONE} 1.2"), &mut list, &mut state);
+        assert_eq!(result, ActionResult::Ok);
+        assert!(list.get(2).unwrap().is_done());
+
+        let result = process(format!("{C_DONE} 2.1"), &mut list, &mut state);
+        assert_eq!(result, ActionResult::TaskNotFound);
+    }
+
+    #[test]
+    fn test_process_done_parent() {
+        let mut list = List::new();
+        let mut state = State::new();
+        tree(&mut list, &mut state);
+        let _ = process(format!("{C_DONE} 1.2"), &mut list, &mut state);
+
+        let result = process(format!("{C_DONE} 1"), &mut list, &mut state);
+        assert_eq!(
+            result,
+            ActionResult::NeedConfirmSubtasks("  1.1) #3 [ ] a1;\n".to_string())
+        );
+        assert!(result
+            .message()
+            .unwrap()
+            .ends_with("complete subtasks too? (y/n)"));
+        assert_eq!(state.status, Some(Status::NeedConfirmation));
+
+        let result = process("maybe".to_string(), &mut list, &mut state);
+        assert_eq!(
+            result,
+            ActionResult::NeedConfirmSubtasks("  1.1) #3 [ ] a1;\n".to_string())
+        );
+        assert_eq!(state.status, Some(Status::NeedConfirmation));
+        assert!(!list.get(0).unwrap().is_done());
+
+        let result = process("n".to_string(), &mut list, &mut state);
+        assert_eq!(result, ActionResult::Ok);
+        assert!(list.get(0).unwrap().is_done());
+        assert!(!list.get(1).unwrap().is_done());
+        assert!(state.status.is_none());
+
+        let _ = process(C_UNDO.to_string(), &mut list, &mut state);
+        let _ = process(format!("{C_DONE} 1"), &mut list, &mut state);
+        let result = process(C_Y.to_string(), &mut list, &mut state);
+        assert_eq!(result, ActionResult::Ok);
+        assert!(list.get(0).unwrap().is_done());
+        assert!(list.get(1).unwrap().is_done());
+        assert!(!list.get(3).unwrap().is_done());
+    }
+
+    #[test]
+    fn test_process_remove_parent() {
+        let mut list = List::new();
+        let mut state = State::new();
+        tree(&mut list, &mut state);
+
+        let result = process(format!("{C_REMOVE} 1"), &mut list, &mut state);
+        assert_eq!(
+            result,
+            ActionResult::NeedConfirmTasks(
+                "1) #1 [ ] a;\n  1.1) #3 [ ] a1;\n  1.2) #4 [ ] a2;\n".to_string()
+            )
+        );
+
+        let result = process(C_Y.to_string(), &mut list, &mut state);
+        assert_eq!(result, ActionResult::Ok);
+        assert!(list.get(0).unwrap().text == "b");
+        assert!(list.get(1).is_err());
+    }
+
+    #[test]
+    fn test_process_block() {
+        let mut list = List::new();
+        let mut state = State::new();
+
+        for text in ["a", "b", "c"] {
+            let _ = list.add(text.to_string());
+        }
+
+        let result = process(format!("{C_BLOCK} 3 {C_ON} 2"), &mut list, &mut state);
+        assert_eq!(result, ActionResult::Ok);
+        let result = process(format!("{C_BLOCK} #2 {C_ON} 1"), &mut list, &mut state);
+        assert_eq!(result, ActionResult::Ok);
+
+        let result = process(format!("{C_BLOCK} 1 {C_ON} 3"), &mut list, &mut state);
+        assert_eq!(result, ActionResult::DependencyCycle);
+        assert_eq!(result.exit_code(), 17);
+
+        let result = process(format!("{C_DONE} 3"), &mut list, &mut state);
+        assert_eq!(
+            result,
+            ActionResult::Blocked("2) #2 [ ] b after:#1 (blocked);\n".to_string())
+        );
+        assert_eq!(
+            result.message().unwrap(),
+            "blocked by:\n2) #2 [ ] b after:#1 (blocked);"
+        );
+        assert!(!list.get(2).unwrap().is_done());
+
+        assert_eq!(
+            process(C_NEXT.to_string(), &mut list, &mut state),
+            ActionResult::Feedback("1) #1 [ ] a;\n".to_string())
+        );
+
+        // Tasks done together may wait for each other.
+        let result = process(format!("{C_DONE} 1 2"), &mut list, &mut state);
+        assert_eq!(result, ActionResult::Ok);
+        let result = process(format!("{C_DONE} 3"), &mut list, &mut state);
+        assert_eq!(result, ActionResult::Ok);
+    }
+
+    #[test]
+    fn test_process_unblock() {
+        let mut list = List::new();
+        let mut state = State::new();
+
+        for text in ["a", "b", "c"] {
+            let _ = list.add(text.to_string());
+        }
+
+        let _ = process(format!("{C_BLOCK} 3 {C_ON} 1"), &mut list, &mut state);
+        let _ = process(format!("{C_BLOCK} 3 {C_ON} 2"), &mut list, &mut state);
+
+        let result = process(format!("{C_UNBLOCK} 3 {C_ON} 1"), &mut list, &mut state);
+        assert_eq!(result, ActionResult::Ok);
+        assert_eq!(list.get(2).unwrap().blocked_by, [2]);
+
+        let result = process(format!("{C_UNBLOCK} 3"), &mut list, &mut state);
+        assert_eq!(result, ActionResult::Ok);
+        assert!(list.get(2).unwrap().blocked_by.is_empty());
+    }
+
+    #[test]
+    fn test_process_block_invalid() {
+        let mut list = List::new();
+        let mut state = State::new();
+        let _ = list.add("a".to_string());
+        let _ = list.add("b".to_string());
+
+        let cases = [
+            (C_BLOCK.to_string(), ParseError::MissingArgument("task")),
+            (
+                format!("{C_BLOCK} 1"),
+                ParseError::MissingArgument("blocking task"),
+            ),
+            (
+                format!("{C_BLOCK} 1 {C_ON}"),
+                ParseError::MissingArgument("blocking task"),
+            ),
+            (
+                format!("{C_BLOCK} 1 2"),
+                ParseError::UnexpectedArgument("2".to_string()),
+            ),
+            (
+                format!("{C_UNBLOCK} 1 {C_ON} 2 3"),
+                ParseError::UnexpectedArgument("3".to_string()),
+            ),
+        ];
+
+        for (input, reason) in cases {
+            let result = process(input, &mut list, &mut state);
+            assert_eq!(result, ActionResult::InvalidArguments(reason));
+        }
+
+        assert_eq!(
+            process(format!("{C_BLOCK} 1 {C_ON} 5"), &mut list, &mut state),
+            ActionResult::TaskNotFound
+        );
+    }
+
+    #[test]
+    fn test_process_repeat() {
+        let mut list = List::new();
+        let mut state = State::new();
+        let _ = list.add("report".to_string());
+
+        let result = process(format!("{C_REPEAT} 1 monthly:15"), &mut list, &mut state);
+        assert_eq!(result, ActionResult::Ok);
+        assert_eq!(
+            list.get(0).unwrap().recurrence,
+            Some(Recurrence::Monthly(15))
+        );
+
+        let result = process(format!("{C_DONE} 1"), &mut list, &mut state);
+        assert_eq!(result, ActionResult::Ok);
+        assert!(list.get(0).unwrap().is_done());
+        assert!(!list.get(1).unwrap().is_done());
+        assert_eq!(list.get(1).unwrap().due.unwrap().ymd().2, 15);
+
+        let result = process(format!("{C_REPEAT} 2 {C_NONE}"), &mut list, &mut state);
+        assert_eq!(result, ActionResult::Ok);
+        assert!(list.get(1).unwrap().recurrence.is_none());
+    }
+
+    #[test]
+    fn test_process_repeat_invalid() {
+        let mut list = List::new();
+        let mut state = State::new();
+        let _ = list.add("report".to_string());
+
+        let cases = [
+            (C_REPEAT.to_string(), ParseError::MissingArgument("task")),
+            (
+                format!("{C_REPEAT} 1"),
+                ParseError::MissingArgument("recurrence"),
+            ),
+            (
+                format!("{C_REPEAT} 1 hourly"),
+                ParseError::InvalidRecurrence("hourly".to_string()),
+            ),
+            (
+                format!("{C_REPEAT} 1 daily now"),
+                ParseError::UnexpectedArgument("now".to_string()),
+            ),
+        ];
+
+        for (input, reason) in cases {
+            let result = process(input, &mut list, &mut state);
+            assert_eq!(result, ActionResult::InvalidArguments(reason));
+        }
+
+        assert_eq!(
+            process(format!("{C_REPEAT} 2 daily"), &mut list, &mut state),
+            ActionResult::TaskNotFound
+        );
+    }
+
+    #[test]
+    fn test_process_list_long() {
+        let mut list = List::new();
+        let mut state = State::new();
+        let _ = list.add("a".to_string());
+        let _ = list.add("b".to_string());
+        let _ = list.mark_done(&[1]);
+
+        let result = process(format!("{C_LIST} {O_LONG} {O_DONE}"), &mut list, &mut state);
+        let ActionResult::Feedback(feedback) = result else {
+            panic!("expected a listing, got {result:?}");
+        };
+
+        assert!(feedback.starts_with("2) #2 [+] b created:"));
+        assert!(feedback.contains(" completed:"));
+        assert_eq!(feedback.lines().count(), 1);
+
+        assert_eq!(
+            process(format!("{C_LIST} {O_LONG} {O_LONG}"), &mut list, &mut state),
+            ActionResult::InvalidArguments(ParseError::UnexpectedArgument(O_LONG.to_string()))
+        );
+    }
+
+    #[test]
+    fn test_process_done_not_found() {
+        let mut list = List::new();
+        let mut state = State::new();
+
+        let result = process(format!("{C_DONE} 1"), &mut list, &mut state);
+
+        assert_eq!(result, ActionResult::TaskNotFound);
+    }
+
+    #[test]
+    fn test_process_done_by_id() {
+        let mut list = List::new();
+        let mut state = State::new();
+
+        let _ = list.add("test1".to_string());
+        let _ = list.add("test2".to_string());
+        let _ = list.remove(&[0]);
+
+        let result = process(format!("{C_DONE} #2"), &mut list, &mut state);
+
+        assert_eq!(result, ActionResult::Ok);
+        assert!(list.get(0).unwrap().is_done());
+
+        let result = process(format!("{C_DONE} #1"), &mut list, &mut state);
+        assert_eq!(result, ActionResult::TaskNotFound);
+
+        let result = process(format!("{C_DONE} #x"), &mut list, &mut state);
+        assert_eq!(
+            result,
+            ActionResult::InvalidArguments(ParseError::NotANumber("x".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_process_edit_by_id() {
+        let mut list = List::new();
+        let mut state = State::new();
+
+        let _ = list.add("test1".to_string());
+        let _ = list.add("test2".to_string());
+
+        let result = process(format!("{C_EDIT} #2"), &mut list, &mut state);
+
+        assert_eq!(result, ActionResult::NeedTask);
+        assert_eq!(state.task_index, Some(1));
+
+        let _ = process("new-test".to_string(), &mut list, &mut state);
+        assert!(list.get(1).unwrap().text == "new-test");
+        assert_eq!(list.get(1).unwrap().id, 2);
+    }
+
+    #[test]
+    fn test_process_undone() {
+        let mut list = List::new();
+        let mut state = State::new();
+
+        let _ = list.add("test".to_string());
+        let _ = list.mark_done(&[0]);
+
+        assert!(list.get(0).unwrap().is_done());
+
+        let result = process(format!("{C_UNDONE} 1"), &mut list, &mut state);
+
+        assert!(!list.get(0).unwrap().is_done());
+        assert_eq!(result, ActionResult::Ok);
+    }
+
+    #[test]
+    fn test_process_undone_not_found() {
+        let mut list = List::new();
+        let mut state = State::new();
+
+        let result = process(format!("{C_UNDONE} 1"), &mut list, &mut state);
+
+        assert_eq!(result, ActionResult::TaskNotFound);
+    }
+
+    #[test]
+    fn test_process_status() {
+        let mut list = List::new();
+        let mut state = State::new();
+
+        for text in ["a", "b", "c", "d"] {
+            let _ = list.add(text.to_string());
+        }
+
+        let result = process(format!("{C_START} 1"), &mut list, &mut state);
+        assert_eq!(result, ActionResult::Ok);
+        let result = process(format!("{C_WAIT} 2"), &mut list, &mut state);
+        assert_eq!(result, ActionResult::Ok);
+        let result = process(format!("{C_CANCEL} #3"), &mut list, &mut state);
+        assert_eq!(result, ActionResult::Ok);
+
+        assert_eq!(
+            process(format!("{C_LIST} {O_PENDING}"), &mut list, &mut state),
+            ActionResult::Feedback("1) #1 [>] a;\n2) #2 [~] b;\n4) #4 [ ] d;\n".to_string())
+        );
+        assert_eq!(
+            process(C_NEXT.to_string(), &mut list, &mut state),
+            ActionResult::Feedback("1) #1 [>] a;\n4) #4 [ ] d;\n".to_string())
+        );
+
+        let result = process(format!("{C_REOPEN} 1-3"), &mut list, &mut state);
+        assert_eq!(result, ActionResult::Ok);
+        assert!((0..4).all(|i| list.get(i).unwrap().status == TaskStatus::Todo));
+
+        let _ = process(C_UNDO.to_string(), &mut list, &mut state);
+        assert_eq!(list.get(2).unwrap().status, TaskStatus::Cancelled);
+    }
+
+    #[test]
+    fn test_process_start_blocked() {
+        let mut list = List::new();
+        let mut state = State::new();
+
+        for text in ["a", "b"] {
+            let _ = list.add(text.to_string());
+        }
+        let _ = list.block(1, 0);
+
+        let result = process(format!("{C_START} 2"), &mut list, &mut state);
+        assert!(matches!(result, ActionResult::Blocked(..)));
+        assert_eq!(list.get(1).unwrap().status, TaskStatus::Todo);
+
+        let result = process(format!("{C_WAIT} 2"), &mut list, &mut state);
+        assert_eq!(result, ActionResult::Ok);
+        let result = process(format!("{C_START} 3"), &mut list, &mut state);
+        assert_eq!(result, ActionResult::TaskNotFound);
+    }
+
+    #[test]
+    fn test_process_done_skips_cancelled_subtasks() {
+        let mut list = List::new();
+        let mut state = State::new();
+
+        let _ = list.add("a".to_string());
+        let _ = list.add_subtask(0, "b".to_string());
+        let _ = list.add_subtask(0, "c".to_string());
+        let _ = list.set_status(&[1], TaskStatus::Cancelled);
+
+        let result = process(format!("{C_DONE} 1"), &mut list, &mut state);
+        assert!(matches!(result, ActionResult::NeedConfirmSubtasks(..)));
+        let result = process(C_Y.to_string(), &mut list, &mut state);
+        assert_eq!(result, ActionResult::Ok);
+
+        assert_eq!(list.get(1).unwrap().status, TaskStatus::Cancelled);
+        assert_eq!(list.get(2).unwrap().status, TaskStatus::Done);
+    }
+
+    #[test]
+    fn test_process_archive() {
+        let mut list = List::new();
+        let mut state = State::new();
+
+        for text in ["buy milk", "write report", "buy bread"] {
+            let _ = list.add(text.to_string());
+        }
+
+        let result = process(C_ARCHIVE.to_string(), &mut list, &mut state);
+        assert_eq!(result, ActionResult::NothingToArchive);
+        assert_eq!(result.exit_code(), 19);
+
+        let _ = process(format!("{C_DONE} 1 3"), &mut list, &mut state);
+
+        // Nothing was done before today.
+        let result = process(format!("{C_ARCHIVE} {O_OLDER} 0"), &mut list, &mut state);
+        assert_eq!(result, ActionResult::NothingToArchive);
+
+        let result = process(C_ARCHIVE.to_string(), &mut list, &mut state);
+        assert_eq!(result, ActionResult::Ok);
+        assert_eq!(list.indices().len(), 1);
+
+        let ActionResult::Feedback(archived) =
+            process(format!("{C_ARCHIVE} {C_LIST}"), &mut list, &mut state)
+        else {
+            panic!("expected the archive");
+        };
+        assert!(archived.starts_with("1) #1 [+] buy milk created:"));
+        assert!(archived.contains("\n2) #3 [+] buy bread created:"));
+
+        let ActionResult::Feedback(found) = process(
+            format!("{C_ARCHIVE} {C_SEARCH} BREAD"),
+            &mut list,
+            &mut state,
+        ) else {
+            panic!("expected the tasks found");
+        };
+        assert!(found.starts_with("2) #3 [+] buy bread created:"));
+        assert!(found.contains(" completed:"));
+        assert_eq!(found.lines().count(), 1);
+
+        let result = process(format!("{C_UNARCHIVE} #3"), &mut list, &mut state);
+        assert_eq!(result, ActionResult::Ok);
+        assert_eq!(list.get(1).unwrap().text, "buy bread");
+
+        let result = process(format!("{C_UNARCHIVE} 2"), &mut list, &mut state);
+        assert_eq!(result, ActionResult::TaskNotFound);
+    }
+
+    #[test]
+    fn test_process_archive_invalid() {
+        let mut list = List::new();
+        let mut state = State::new();
+
+        let cases = [
+            (
+                format!("{C_ARCHIVE} {O_OLDER}"),
+                ParseError::MissingArgument("days"),
+            ),
+            (
+                format!("{C_ARCHIVE} {O_OLDER} -1"),
+                ParseError::NotANumber("-1".to_string()),
+            ),
+            (
+                format!("{C_ARCHIVE} 3"),
+                ParseError::UnexpectedArgument("3".to_string()),
+            ),
+            (
+                format!("{C_ARCHIVE} {C_LIST} all"),
+                ParseError::UnexpectedArgument("all".to_string()),
+            ),
+            (
+                format!("{C_ARCHIVE} {C_SEARCH}"),
+                ParseError::MissingArgument("query"),
+            ),
+            (C_UNARCHIVE.to_string(), ParseError::MissingArgument("task")),
+            (
+                format!("{C_UNARCHIVE} 1 2"),
+                ParseError::UnexpectedArgument("2".to_string()),
+            ),
+        ];
+
+        for (input, error) in cases {
+            assert_eq!(
+                process(input.clone(), &mut list, &mut state),
+                ActionResult::InvalidArguments(error),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_process_trash() {
+        let mut list = List::new();
+        let mut state = State::new();
+
+        for text in ["a", "b", "c"] {
+            let _ = list.add(text.to_string());
+        }
+
+        let _ = process(format!("{C_REMOVE} 2"), &mut list, &mut state);
+        let result = process(C_YES.to_string(), &mut list, &mut state);
+        assert_eq!(result, ActionResult::Ok);
+
+        let ActionResult::Feedback(trash) = process(C_TRASH.to_string(), &mut list, &mut state)
+        else {
+            panic!("expected the trash");
+        };
+        assert!(trash.starts_with("1) #2 [ ] b removed:"));
+
+        let result = process(format!("{C_RESTORE} 1"), &mut list, &mut state);
+        assert_eq!(result, ActionResult::Ok);
+        assert_eq!(list.get(1).unwrap().text, "b");
+
+        let result = process(format!("{C_RESTORE} 1"), &mut list, &mut state);
+        assert_eq!(result, ActionResult::TaskNotFound);
+
+        let _ = process(format!("{C_REMOVE} 2"), &mut list, &mut state);
+        let _ = process(C_YES.to_string(), &mut list, &mut state);
+        let result = process(format!("{C_TRASH} {C_RESTORE} 1"), &mut list, &mut state);
+        assert_eq!(result, ActionResult::Ok);
+        assert_eq!(list.get(1).unwrap().text, "b");
+
+        let _ = process(C_CLEAR.to_string(), &mut list, &mut state);
+        let _ = process(C_YES.to_string(), &mut list, &mut state);
+        let result = process(format!("{C_TRASH} {C_EMPTY}"), &mut list, &mut state);
+        assert_eq!(result, ActionResult::Ok);
+        assert_eq!(
+            process(C_TRASH.to_string(), &mut list, &mut state),
+            ActionResult::Feedback(String::new())
+        );
+    }
+
+    #[test]
+    fn test_process_trash_invalid() {
+        let mut list = List::new();
+        let mut state = State::new();
+
+        let cases = [
+            (
+                format!("{C_TRASH} all"),
+                ParseError::UnexpectedArgument("all".to_string()),
+            ),
+            (
+                format!("{C_TRASH} {C_EMPTY} now"),
+                ParseError::UnexpectedArgument("now".to_string()),
+            ),
+            (
+                format!("{C_TRASH} {C_RESTORE}"),
+                ParseError::MissingArgument("entry"),
+            ),
+            (format!("{C_TRASH} {C_RESTORE} 0"), ParseError::ZeroIndex),
+            (
+                format!("{C_TRASH} {C_RESTORE} 1 2"),
+                ParseError::UnexpectedArgument("2".to_string()),
+            ),
+            (C_RESTORE.to_string(), ParseError::MissingArgument("entry")),
+            (
+                format!("{C_RESTORE} 1 2"),
+                ParseError::UnexpectedArgument("2".to_string()),
+            ),
+        ];
+
+        for (input, error) in cases {
+            assert_eq!(
+                process(input.clone(), &mut list, &mut state),
+                ActionResult::InvalidArguments(error),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_process_prio() {
         let mut list = List::new();
         let mut state = State::new();
 
-        let task_before_edit = "test";
-        let task_after_edit = "new-test";
-
-        let _ = list.add(task_before_edit.to_string());
-
-        assert!(list.get(0).unwrap().text == task_before_edit);
-        let result = process(format!("{C_EDIT} 1"), &mut list, &mut state);
-
-        assert_eq!(result, ActionResult::NeedTask);
-        assert_eq!(state.status, Some(Status::NeedPlainText));
-        assert_eq!(state.command, Some(C_EDIT));
-        assert_eq!(state.task_index, Some(0));
+        let _ = list.add("test".to_string());
 
-        let result = process(task_after_edit.to_string(), &mut list, &mut state);
+        let result = process(format!("{C_PRIO} 1 high"), &mut list, &mut state);
 
         assert_eq!(result, ActionResult::Ok);
-        assert!(list.get(0).unwrap().text == task_after_edit);
-        assert!(state.status.is_none());
-        assert!(state.command.is_none());
-        assert!(state.task_index.is_none());
+        assert_eq!(list.get(0).unwrap().priority, Priority::High);
     }
 
     #[test]
-    fn test_process_edit_not_found() {
+    fn test_process_prio_invalid() {
         let mut list = List::new();
         let mut state = State::new();
 
-        let result = process(format!("{C_EDIT} 1"), &mut list, &mut state);
+        let _ = list.add("test".to_string());
+
+        let result = process(format!("{C_PRIO} 1 urgent"), &mut list, &mut state);
+        assert_eq!(
+            result,
+            ActionResult::InvalidArguments(ParseError::InvalidPriority("urgent".to_string()))
+        );
 
+        let result = process(format!("{C_PRIO} high 1"), &mut list, &mut state);
+        assert_eq!(
+            result,
+            ActionResult::InvalidArguments(ParseError::NotANumber("high".to_string()))
+        );
+
+        let result = process(format!("{C_PRIO} 1"), &mut list, &mut state);
+        assert_eq!(
+            result,
+            ActionResult::InvalidArguments(ParseError::MissingArgument("priority"))
+        );
+
+        let result = process(format!("{C_PRIO} 2 low"), &mut list, &mut state);
         assert_eq!(result, ActionResult::TaskNotFound);
-        assert!(state.status.is_none());
-        assert!(state.command.is_none());
-        assert!(state.task_index.is_none());
     }
 
     #[test]
-    fn test_process_edit_invalid() {
+    fn test_process_list_prio() {
         let mut list = List::new();
         let mut state = State::new();
 
-        let result = process(format!("{C_EDIT} test"), &mut list, &mut state);
+        let _ = list.add("test1".to_string());
+        let _ = list.add("test2".to_string());
+        let _ = list.set_priority(1, Priority::Medium);
 
-        assert_eq!(result, ActionResult::InvalidArguments);
-        assert!(state.status.is_none());
-        assert!(state.command.is_none());
-        assert!(state.task_index.is_none());
+        let expected = "2) #2 [ ] (M) test2;\n1) #1 [ ] test1;\n".to_string();
+        let result = process(format!("{C_LIST} {C_PRIO}"), &mut list, &mut state);
+
+        assert_eq!(result, ActionResult::Feedback(expected));
     }
 
     #[test]
-    fn test_process_remove() {
+    fn test_process_due() {
         let mut list = List::new();
         let mut state = State::new();
 
         let _ = list.add("test".to_string());
 
-        assert!(!list.is_empty());
-        let result = process(format!("{C_REMOVE} 1"), &mut list, &mut state);
+        let result = process(format!("{C_DUE} 1 2026-11-01"), &mut list, &mut state);
+        assert_eq!(result, ActionResult::Ok);
+        assert_eq!(list.get(0).unwrap().due, date::Date::from_ymd(2026, 11, 1));
 
-        assert_eq!(result, ActionResult::NeedConfirm);
-        assert_eq!(state.command, Some(C_REMOVE));
-        assert_eq!(state.status, Some(Status::NeedConfirmation));
-        assert_eq!(state.task_index, Some(0));
+        let result = process(format!("{C_DUE} 1 tomorrow"), &mut list, &mut state);
+        assert_eq!(result, ActionResult::Ok);
+        assert_eq!(list.get(0).unwrap().due, date::today().add_days(1));
 
-        let result = process(C_YES.to_string(), &mut list, &mut state);
+        let result = process(format!("{C_DUE} 1 next fri"), &mut list, &mut state);
+        assert_eq!(result, ActionResult::Ok);
+        assert_eq!(list.get(0).unwrap().due.unwrap().weekday(), 4);
 
+        let result = process(format!("{C_DUE} 1 {C_NONE}"), &mut list, &mut state);
         assert_eq!(result, ActionResult::Ok);
-        assert!(list.is_empty());
-        assert!(state.status.is_none());
-        assert!(state.command.is_none());
-        assert!(state.task_index.is_none());
+        assert!(list.get(0).unwrap().due.is_none());
     }
 
     #[test]
-    fn test_process_remove_decline() {
+    fn test_process_due_invalid() {
         let mut list = List::new();
         let mut state = State::new();
-        let task = "test";
-
-        let _ = list.add(task.to_string());
 
-        assert!(list.get(0).unwrap().text == task);
-        let result = process(format!("{C_REMOVE} 1"), &mut list, &mut state);
+        let _ = list.add("test".to_string());
 
-        assert_eq!(result, ActionResult::NeedConfirm);
-        assert_eq!(state.command, Some(C_REMOVE));
-        assert_eq!(state.status, Some(Status::NeedConfirmation));
-        assert_eq!(state.task_index, Some(0));
+        let result = process(format!("{C_DUE} 1 someday"), &mut list, &mut state);
+        assert_eq!(
+            result,
+            ActionResult::InvalidArguments(ParseError::InvalidDate("someday".to_string()))
+        );
 
-        let result = process("n".to_string(), &mut list, &mut state);
+        let result = process(format!("{C_DUE} 1"), &mut list, &mut state);
+        assert_eq!(
+            result,
+            ActionResult::InvalidArguments(ParseError::MissingArgument("date"))
+        );
 
-        assert_eq!(result, ActionResult::Sh);
-        assert!(list.get(0).unwrap().text == task);
-        assert!(state.status.is_none());
-        assert!(state.command.is_none());
-        assert!(state.task_index.is_none());
+        let result = process(format!("{C_DUE} 2 today"), &mut list, &mut state);
+        assert_eq!(result, ActionResult::TaskNotFound);
     }
 
     #[test]
-    fn test_process_remove_not_found() {
+    fn test_process_today_overdue() {
         let mut list = List::new();
         let mut state = State::new();
+        let today = date::today();
 
-        let result = process(format!("{C_REMOVE} 1"), &mut list, &mut state);
+        let _ = list.add("test1".to_string());
+        let _ = list.add("test2".to_string());
+        let _ = list.add("test3".to_string());
+        let _ = list.set_due(0, today.add_days(-2));
+        let _ = list.set_due(1, Some(today));
 
-        assert_eq!(result, ActionResult::TaskNotFound);
-        assert!(state.status.is_none());
-        assert!(state.command.is_none());
-        assert!(state.task_index.is_none());
+        let expected = format!("2) #2 [ ] test2 due:{today} (due today);\n");
+        let result = process(C_TODAY.to_string(), &mut list, &mut state);
+        assert_eq!(result, ActionResult::Feedback(expected));
+
+        let expected = format!(
+            "1) #1 [ ] test1 due:{} (overdue);\n",
+            today.add_days(-2).unwrap()
+        );
+        let result = process(C_OVERDUE.to_string(), &mut list, &mut state);
+        assert_eq!(result, ActionResult::Feedback(expected));
     }
 
     #[test]
-    fn test_process_remove_invalid() {
+    fn test_process_add_tags() {
         let mut list = List::new();
         let mut state = State::new();
 
-        let result = process(format!("{C_REMOVE} 1 two"), &mut list, &mut state);
+        let _ = process(C_ADD.to_string(), &mut list, &mut state);
+        let result = process("call mom +family @phone".to_string(), &mut list, &mut state);
 
-        assert_eq!(result, ActionResult::InvalidArguments);
-        assert!(state.status.is_none());
-        assert!(state.command.is_none());
-        assert!(state.task_index.is_none());
+        assert_eq!(result, ActionResult::Ok);
+        assert!(list.get(0).unwrap().text == "call mom");
+        assert_eq!(list.get(0).unwrap().projects, vec!["family"]);
+        assert_eq!(list.get(0).unwrap().contexts, vec!["phone"]);
     }
 
     #[test]
-    fn test_process_done() {
+    fn test_process_list_options() {
         let mut list = List::new();
         let mut state = State::new();
 
-        let _ = list.add("test".to_string());
+        let _ = list.add("b +work".to_string());
+        let _ = list.add("c".to_string());
+        let _ = list.add("a +work".to_string());
+        let _ = list.add("d".to_string());
+        let _ = list.mark_done(&[1]);
+
+        let result = process(format!("{C_LIST} {O_PENDING}"), &mut list, &mut state);
+        assert_eq!(
+            result,
+            ActionResult::Feedback(
+                "1) #1 [ ] b +work;\n3) #3 [ ] a +work;\n4) #4 [ ] d;\n".to_string()
+            )
+        );
 
-        assert!(!list.get(0).unwrap().is_done);
+        let result = process(format!("{C_LIST} {O_DONE}"), &mut list, &mut state);
+        assert_eq!(result, ActionResult::Feedback("2) #2 [+] c;\n".to_string()));
 
-        let result = process(format!("{C_DONE} 1"), &mut list, &mut state);
+        let result = process(
+            format!("{C_LIST} {O_SORT} text {O_REVERSE} {O_LIMIT} 2"),
+            &mut list,
+            &mut state,
+        );
+        assert_eq!(
+            result,
+            ActionResult::Feedback("4) #4 [ ] d;\n2) #2 [+] c;\n".to_string())
+        );
 
-        assert!(list.get(0).unwrap().is_done);
-        assert_eq!(result, ActionResult::Ok);
+        let result = process(
+            format!("{C_LIST} +work {O_PENDING} {O_SORT} created {O_REVERSE}"),
+            &mut list,
+            &mut state,
+        );
+        assert_eq!(
+            result,
+            ActionResult::Feedback("3) #3 [ ] a +work;\n1) #1 [ ] b +work;\n".to_string())
+        );
+
+        let result = process(format!("{C_LIST} {O_LIMIT} 0"), &mut list, &mut state);
+        assert_eq!(result, ActionResult::Feedback(String::new()));
     }
 
     #[test]
-    fn test_process_done_not_found() {
+    fn test_process_list_options_invalid() {
         let mut list = List::new();
         let mut state = State::new();
 
-        let result = process(format!("{C_DONE} 1"), &mut list, &mut state);
-
-        assert_eq!(result, ActionResult::TaskNotFound);
+        let cases = [
+            (
+                format!("{C_LIST} --all"),
+                ParseError::UnknownOption("--all".to_string()),
+            ),
+            (
+                format!("{C_LIST} {O_SORT}"),
+                ParseError::MissingArgument("sort key"),
+            ),
+            (
+                format!("{C_LIST} {O_SORT} size"),
+                ParseError::InvalidSortKey("size".to_string()),
+            ),
+            (
+                format!("{C_LIST} {O_LIMIT}"),
+                ParseError::MissingArgument("limit"),
+            ),
+            (
+                format!("{C_LIST} {O_LIMIT} -1"),
+                ParseError::NotANumber("-1".to_string()),
+            ),
+            (
+                format!("{C_LIST} {O_PENDING} {O_DONE}"),
+                ParseError::UnexpectedArgument(O_DONE.to_string()),
+            ),
+            (
+                format!("{C_LIST} {C_PRIO} {O_SORT} due"),
+                ParseError::UnexpectedArgument(O_SORT.to_string()),
+            ),
+            (
+                format!("{C_LIST} -- {O_DONE}"),
+                ParseError::InvalidTag(O_DONE.to_string()),
+            ),
+        ];
+
+        for (input, reason) in cases {
+            let result = process(input, &mut list, &mut state);
+            assert_eq!(result, ActionResult::InvalidArguments(reason));
+        }
     }
 
     #[test]
-    fn test_process_undone() {
+    fn test_process_list_tags() {
         let mut list = List::new();
         let mut state = State::new();
 
-        let _ = list.add("test".to_string());
-        let _ = list.mark_done(0);
+        let _ = list.add("test1 +work".to_string());
+        let _ = list.add("test2 @home".to_string());
+        let _ = list.add("test3 +work @home".to_string());
+        let _ = list.set_priority(2, Priority::High);
 
-        assert!(list.get(0).unwrap().is_done);
+        let expected = "1) #1 [ ] test1 +work;\n3) #3 [ ] (H) test3 +work @home;\n".to_string();
+        let result = process(format!("{C_LIST} +work"), &mut list, &mut state);
+        assert_eq!(result, ActionResult::Feedback(expected));
 
-        let result = process(format!("{C_UNDONE} 1"), &mut list, &mut state);
+        let expected = "3) #3 [ ] (H) test3 +work @home;\n2) #2 [ ] test2 @home;\n".to_string();
+        let result = process(format!("{C_LIST} {C_PRIO} @home"), &mut list, &mut state);
+        assert_eq!(result, ActionResult::Feedback(expected));
 
-        assert!(!list.get(0).unwrap().is_done);
-        assert_eq!(result, ActionResult::Ok);
+        let result = process(format!("{C_LIST} work"), &mut list, &mut state);
+        assert_eq!(
+            result,
+            ActionResult::InvalidArguments(ParseError::InvalidTag("work".to_string()))
+        );
     }
 
     #[test]
-    fn test_process_undone_not_found() {
+    fn test_process_projects_contexts() {
         let mut list = List::new();
         let mut state = State::new();
 
-        let result = process(format!("{C_UNDONE} 1"), &mut list, &mut state);
+        let _ = list.add("test1 +work".to_string());
+        let _ = list.add("test2 +home @phone".to_string());
+        let _ = list.add("test3 +work @phone".to_string());
 
-        assert_eq!(result, ActionResult::TaskNotFound);
+        let expected = "+home (1)\n+work (2)\n".to_string();
+        let result = process(C_PROJECTS.to_string(), &mut list, &mut state);
+        assert_eq!(result, ActionResult::Feedback(expected));
+
+        let expected = "@phone (2)\n".to_string();
+        let result = process(C_CONTEXTS.to_string(), &mut list, &mut state);
+        assert_eq!(result, ActionResult::Feedback(expected));
     }
 
     #[test]
@@ -553,7 +2968,7 @@ mod tests {
 
         assert!(!list.is_empty());
 
-        let result = process(format!("{C_CLEAR}"), &mut list, &mut state);
+        let result = process(C_CLEAR.to_string(), &mut list, &mut state);
 
         assert_eq!(result, ActionResult::NeedConfirm);
         assert_eq!(state.command, Some(C_CLEAR));
@@ -578,7 +2993,7 @@ mod tests {
 
         assert!(!list.is_empty());
 
-        let result = process(format!("{C_CLEAR}"), &mut list, &mut state);
+        let result = process(C_CLEAR.to_string(), &mut list, &mut state);
 
         assert_eq!(result, ActionResult::NeedConfirm);
         assert_eq!(state.command, Some(C_CLEAR));
@@ -599,7 +3014,7 @@ mod tests {
         let mut list = List::new();
         let mut state = State::new();
 
-        let result = process(format!("{C_CLEAR}"), &mut list, &mut state);
+        let result = process(C_CLEAR.to_string(), &mut list, &mut state);
 
         assert_eq!(result, ActionResult::ListEmpty);
         assert!(state.status.is_none());
@@ -607,28 +3022,81 @@ mod tests {
         assert!(state.task_index.is_none());
     }
 
+    #[test]
+    fn test_process_undo_redo() {
+        let mut list = List::new();
+        let mut state = State::new();
+
+        let result = process(C_UNDO.to_string(), &mut list, &mut state);
+        assert_eq!(result, ActionResult::NothingToUndo);
+
+        let _ = list.add("test".to_string());
+        let _ = process(C_CLEAR.to_string(), &mut list, &mut state);
+        let _ = process(C_YES.to_string(), &mut list, &mut state);
+        assert!(list.is_empty());
+
+        let result = process(C_UNDO.to_string(), &mut list, &mut state);
+        assert_eq!(result, ActionResult::Ok);
+        assert!(list.get(0).unwrap().text == "test");
+
+        let result = process(C_REDO.to_string(), &mut list, &mut state);
+        assert_eq!(result, ActionResult::Ok);
+        assert!(list.is_empty());
+
+        let result = process(C_REDO.to_string(), &mut list, &mut state);
+        assert_eq!(result, ActionResult::NothingToRedo);
+    }
+
+    #[test]
+    fn test_process_undo_load() {
+        let mut list = List::new();
+        let mut state = State::new();
+
+        let path = std::env::temp_dir().join("todocli_test_process_undo_load.txt");
+        let path = path.to_string_lossy().to_string();
+        let _ = fs::write(&path, "1) [ ] learn rust;\n2) [+] learn javascript;\n");
+
+        let _ = list.add("test".to_string());
+        let _ = process(C_LOAD.to_string(), &mut list, &mut state);
+        let result = process(path.clone(), &mut list, &mut state);
+
+        assert_eq!(result, ActionResult::Ok);
+        assert!(list.get(0).unwrap().text == "learn rust");
+        let _ = fs::remove_file(&path);
+
+        let result = process(C_UNDO.to_string(), &mut list, &mut state);
+
+        assert_eq!(result, ActionResult::Ok);
+        assert!(list.get(0).unwrap().text == "test");
+        assert!(list.get(1).is_err());
+    }
+
     #[test]
     fn test_process_save() {
         let mut list = List::new();
         let mut state = State::new();
+        let path = std::env::temp_dir().join("todocli_test_process_save.txt");
+        let path = path.to_string_lossy().to_string();
 
         let _ = list.add("learn rust".to_string());
         let _ = list.add("learn javascript".to_string());
-        let _ = list.mark_done(1);
+        let _ = list.mark_done(&[1]);
 
-        let result = process(format!("{C_SAVE}"), &mut list, &mut state);
+        let result = process(C_SAVE.to_string(), &mut list, &mut state);
 
         assert_eq!(result, ActionResult::NeedFilePath);
         assert_eq!(state.command, Some(C_SAVE));
         assert_eq!(state.status, Some(Status::NeedPlainText));
         assert_eq!(state.task_index, None);
 
-        let result = process("./test_process_save.txt".to_string(), &mut list, &mut state);
+        let result = process(path.clone(), &mut list, &mut state);
 
         assert_eq!(result, ActionResult::Ok);
         assert!(state.status.is_none());
         assert!(state.command.is_none());
         assert!(state.task_index.is_none());
+
+        let _ = std::fs::remove_file(&path);
     }
 
     #[test]
@@ -636,7 +3104,7 @@ mod tests {
         let mut list = List::new();
         let mut state = State::new();
 
-        let result = process(format!("{C_SAVE}"), &mut list, &mut state);
+        let result = process(C_SAVE.to_string(), &mut list, &mut state);
 
         assert_eq!(result, ActionResult::ListEmpty);
         assert!(state.status.is_none());
@@ -649,14 +3117,14 @@ mod tests {
         let mut list = List::new();
         let mut state = State::new();
 
-        let result = process(format!("{C_LOAD}"), &mut list, &mut state);
+        let result = process(C_LOAD.to_string(), &mut list, &mut state);
 
         assert_eq!(result, ActionResult::NeedFilePath);
         assert_eq!(state.command, Some(C_LOAD));
         assert_eq!(state.status, Some(Status::NeedPlainText));
         assert_eq!(state.task_index, None);
 
-        let result = process("./test_process_save.txt".to_string(), &mut list, &mut state);
+        let result = process("./test_process_load.txt".to_string(), &mut list, &mut state);
 
         assert_eq!(result, ActionResult::Ok);
         assert!(state.status.is_none());
@@ -665,8 +3133,268 @@ mod tests {
 
         assert!(list.get(0).unwrap().text == "learn rust");
         assert!(list.get(1).unwrap().text == "learn javascript");
-        assert!(!list.get(0).unwrap().is_done);
-        assert!(list.get(1).unwrap().is_done);
+        assert!(!list.get(0).unwrap().is_done());
+        assert!(list.get(1).unwrap().is_done());
+    }
+
+    #[test]
+    fn test_exit_code() {
+        assert_eq!(ActionResult::Ok.exit_code(), 0);
+        assert_eq!(ActionResult::Feedback("".to_string()).exit_code(), 0);
+
+        let errors = vec![
+            ActionResult::ListFull,
+            ActionResult::ListEmpty,
+            ActionResult::TaskNotFound,
+            ActionResult::FileReadError,
+            ActionResult::UnknownCommand,
+            ActionResult::InvalidArguments(ParseError::ZeroIndex),
+            ActionResult::NeedConfirm,
+            ActionResult::NeedFilePath,
+            ActionResult::NeedTask,
+            ActionResult::CannotSave,
+            ActionResult::CannotLoad,
+            ActionResult::NothingToUndo,
+            ActionResult::NothingToRedo,
+            ActionResult::BackupNotFound,
+            ActionResult::ViewNotFound,
+            ActionResult::DependencyCycle,
+            ActionResult::Blocked("".to_string()),
+            ActionResult::NothingToArchive,
+            ActionResult::ListNotFound,
+            ActionResult::ListExists,
+            ActionResult::ListInUse,
+            ActionResult::InteractiveOnly(C_UNDO.to_string()),
+            ActionResult::InvalidInput(ParseError::DanglingEscape),
+        ];
+
+        let codes: HashSet<i32> = errors.iter().map(|e| e.exit_code()).collect();
+        assert!(codes.iter().all(|c| *c != 0));
+        assert_eq!(codes.len(), errors.len());
+    }
+
+    #[test]
+    fn test_process_save_load_json() {
+        let mut list = List::new();
+        let mut state = State::new();
+        let path = std::env::temp_dir().join("todocli_test_process_save_load.json");
+        let path = path.to_string_lossy().to_string();
+
+        let _ = list.add("learn [rust]; now".to_string());
+        let _ = list.mark_done(&[0]);
+
+        let _ = process(C_SAVE.to_string(), &mut list, &mut state);
+        let result = process(path.clone(), &mut list, &mut state);
+
+        assert_eq!(result, ActionResult::Ok);
+        assert!(fs::read_to_string(&path).unwrap().contains("\"version\""));
+
+        // Saving over a file of one's own keeps no backups next to it.
+        let _ = list.add("learn go".to_string());
+        let result = process(format!("{C_SAVE} {path}"), &mut list, &mut state);
+        assert_eq!(result, ActionResult::Ok);
+        assert!(storage::backups(&path).is_empty());
+
+        let mut list = List::new();
+
+        let _ = process(C_LOAD.to_string(), &mut list, &mut state);
+        let result = process(path.clone(), &mut list, &mut state);
+
+        assert_eq!(result, ActionResult::Ok);
+        assert!(list.get(0).unwrap().text == "learn [rust]; now");
+        assert!(list.get(0).unwrap().is_done());
+
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_process_backups() {
+        let dir = std::env::temp_dir().join("todocli_test_process_backups");
+        let _ = fs::remove_dir_all(&dir);
+        let path = dir.join("list.json").to_string_lossy().to_string();
+
+        let mut list = List::new();
+        let mut state = State::new().with_data_path(&path).with_backups(2);
+
+        let result = process(C_BACKUPS.to_string(), &mut list, &mut state);
+        assert_eq!(result, ActionResult::Feedback("".to_string()));
+
+        let _ = list.add("test1".to_string());
+        let _ = storage::save(&path, &list, 2);
+        let _ = list.add("test2".to_string());
+        let _ = storage::save(&path, &list, 2);
+
+        let expected = format!("1) {}\n", storage::backups(&path)[0]);
+        let result = process(C_BACKUPS.to_string(), &mut list, &mut state);
+        assert_eq!(result, ActionResult::Feedback(expected));
+
+        // Restoring is how a list that could not be read is recovered.
+        state.autosave = false;
+        let result = process(format!("{C_BACKUPS} {C_RESTORE} 1"), &mut list, &mut state);
+        assert_eq!(result, ActionResult::Ok);
+        assert!(list.get(0).unwrap().text == "test1");
+        assert!(list.get(1).is_err());
+        assert!(state.autosave);
+
+        let result = process(format!("{C_BACKUPS} {C_RESTORE} 2"), &mut list, &mut state);
+        assert_eq!(result, ActionResult::BackupNotFound);
+
+        let result = process(format!("{C_BACKUPS} {C_RESTORE} x"), &mut list, &mut state);
+        assert_eq!(
+            result,
+            ActionResult::InvalidArguments(ParseError::NotANumber("x".to_string()))
+        );
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_process_lists() {
+        let dir = std::env::temp_dir().join("todocli_test_process_lists");
+        let _ = fs::remove_dir_all(&dir);
+        let path = dir.join("list.txt").to_string_lossy().to_string();
+
+        let mut list = List::new();
+        let mut state = State::new().with_data_path(&path).with_backups(0);
+
+        let _ = list.add("report".to_string());
+        let _ = list.add("milk".to_string());
+        let _ = list.add_subtask(1, "oat".to_string());
+
+        let result = process(
+            format!("{C_LIST} {C_CREATE} groceries"),
+            &mut list,
+            &mut state,
+        );
+        assert_eq!(result, ActionResult::Ok);
+        let result = process(
+            format!("{C_LIST} {C_CREATE} groceries"),
+            &mut list,
+            &mut state,
+        );
+        assert_eq!(result, ActionResult::ListExists);
+        let result = process(format!("{C_LIST} {C_CREATE} old"), &mut list, &mut state);
+        assert_eq!(result, ActionResult::Ok);
+
+        let result = process(C_LISTS.to_string(), &mut list, &mut state);
+        assert_eq!(
+            result,
+            ActionResult::Feedback("* default\n  groceries\n  old\n".to_string())
+        );
+
+        let result = process(
+            format!("{C_MOVE} 2 {C_TO} groceries"),
+            &mut list,
+            &mut state,
+        );
+        assert_eq!(result, ActionResult::Ok);
+        assert_eq!(list.indices().len(), 1);
+        assert_eq!(
+            process(C_UNDO.to_string(), &mut list, &mut state),
+            ActionResult::NothingToUndo
+        );
+
+        let result = process(format!("{C_USE} groceries"), &mut list, &mut state);
+        assert_eq!(result, ActionResult::Sh);
+        assert_eq!(state.list_name(), "groceries");
+        let result = process(format!("{C_USE} groceries"), &mut list, &mut state);
+        assert_eq!(result, ActionResult::Sh);
+        assert!(list.get(0).unwrap().text == "milk");
+        assert!(list.get(1).unwrap().text == "oat");
+        assert_eq!(
+            process(C_UNDO.to_string(), &mut list, &mut state),
+            ActionResult::NothingToUndo
+        );
+
+        // The list left behind was saved on the way.
+        let saved = fs::read_to_string(&path).unwrap();
+        assert!(saved.contains("report") && !saved.contains("milk"));
+
+        let result = process(
+            format!("{C_LIST} {C_DELETE} groceries"),
+            &mut list,
+            &mut state,
+        );
+        assert_eq!(result, ActionResult::ListInUse);
+        let result = process(
+            format!("{C_MOVE} 1 {C_TO} groceries"),
+            &mut list,
+            &mut state,
+        );
+        assert_eq!(result, ActionResult::ListInUse);
+
+        let result = process(
+            format!("{C_USE} {DEFAULT_LIST_NAME}"),
+            &mut list,
+            &mut state,
+        );
+        assert_eq!(result, ActionResult::Sh);
+        assert!(list.get(0).unwrap().text == "report");
+
+        let result = process(format!("{C_LIST} {C_DELETE} old"), &mut list, &mut state);
+        assert_eq!(result, ActionResult::Ok);
+        let result = process(format!("{C_USE} old"), &mut list, &mut state);
+        assert_eq!(result, ActionResult::ListNotFound);
+        let result = process(
+            format!("{C_LIST} {C_DELETE} {DEFAULT_LIST_NAME}"),
+            &mut list,
+            &mut state,
+        );
+        assert_eq!(result, ActionResult::ListInUse);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_process_lists_invalid() {
+        let mut list = List::new();
+        let mut state = State::new();
+        let _ = list.add("report".to_string());
+
+        let cases = [
+            (C_USE.to_string(), ParseError::MissingArgument("list name")),
+            (
+                format!("{C_USE} a b"),
+                ParseError::UnexpectedArgument("b".to_string()),
+            ),
+            (
+                format!("{C_USE} a.b"),
+                ParseError::InvalidListName("a.b".to_string()),
+            ),
+            (
+                format!("{C_LIST} {C_CREATE}"),
+                ParseError::MissingArgument("list name"),
+            ),
+            (
+                format!("{C_LIST} {C_DELETE} a b"),
+                ParseError::UnexpectedArgument("b".to_string()),
+            ),
+            (C_MOVE.to_string(), ParseError::MissingArgument("task")),
+            (
+                format!("{C_MOVE} 1 {C_TO}"),
+                ParseError::MissingArgument("list name"),
+            ),
+            (
+                format!("{C_MOVE} 1 work"),
+                ParseError::UnexpectedArgument("work".to_string()),
+            ),
+        ];
+
+        cases.into_iter().for_each(|(line, e)| {
+            assert_eq!(
+                process(line, &mut list, &mut state),
+                ActionResult::InvalidArguments(e)
+            );
+        });
+
+        assert_eq!(
+            process(format!("{C_MOVE} 2 {C_TO} work"), &mut list, &mut state),
+            ActionResult::TaskNotFound
+        );
+        assert_eq!(
+            process(format!("{C_MOVE} 1 {C_TO} work"), &mut list, &mut state),
+            ActionResult::ListNotFound
+        );
     }
 
     #[test]
@@ -674,7 +3402,7 @@ mod tests {
         let mut list = List::new();
         let mut state = State::new();
 
-        let result = process(format!("{C_LOAD}"), &mut list, &mut state);
+        let result = process(C_LOAD.to_string(), &mut list, &mut state);
 
         assert_eq!(result, ActionResult::NeedFilePath);
         assert_eq!(state.command, Some(C_LOAD));
@@ -0,0 +1,284 @@
+use crate::{
+    actions::{self, ActionResult},
+    command_parser,
+    config::{Config, C_N, C_REDO, C_UNDO, C_USE, C_Y},
+    models::{list::List, state::State},
+    storage,
+};
+use std::io::{self, IsTerminal};
+
+fn report(result: &ActionResult) {
+    match result {
+        ActionResult::Sh | ActionResult::Ok | ActionResult::Terminate => (),
+        ActionResult::Feedback(feedback) => println!("{feedback}"),
+        _ => {
+            if let Some(message) = result.message() {
+                eprintln!("{message}");
+            }
+        }
+    }
+}
+
+// Runs a single command against the list stored at `path`, or the list
+// called `list_name` next to it, and returns the process exit code.
+// Confirmations are implied by the command itself.
+pub fn run(args: &[String], path: &str, list_name: Option<&str>, config: &Config) -> i32 {
+    // History lives as long as the process, so there is never any to undo,
+    // and a list switched to would be left again right away.
+    if let Some(command @ (C_UNDO | C_REDO | C_USE)) = args.first().map(String::as_str) {
+        let result = ActionResult::InteractiveOnly(command.to_string());
+        self::report(&result);
+        return result.exit_code();
+    }
+
+    let mut list = List::new()
+        .with_limit(config.max_tasks)
+        .with_history(config.history_depth)
+        .with_trash(config.trash_size);
+    let mut state = State::new()
+        .with_data_path(path)
+        .with_backups(config.backups)
+        .with_views(config)
+        .with_highlight(io::stdout().is_terminal());
+
+    if let Some(name) = list_name {
+        match actions::existing_list(name, &state) {
+            Ok((key, _)) => state.list = key,
+            Err(result) => {
+                self::report(&result);
+                return result.exit_code();
+            }
+        }
+    }
+
+    let path = storage::list_path(path, state.list.as_deref());
+    let loaded = storage::load(&path, &mut list);
+
+    if loaded != ActionResult::Ok {
+        self::report(&loaded);
+        return loaded.exit_code();
+    }
+
+    list.clear_history();
+
+    let line: Vec<String> = args.iter().map(|a| command_parser::quote(a)).collect();
+    let mut result = actions::process(line.join(" "), &mut list, &mut state);
+
+    // Completing subtasks was not asked for, so only the tasks named are.
+    let answer = match result {
+        ActionResult::NeedConfirm | ActionResult::NeedConfirmTasks(..) => Some(C_Y),
+        ActionResult::NeedConfirmSubtasks(..) => Some(C_N),
+        _ => None,
+    };
+
+    if let Some(answer) = answer {
+        result = actions::process(answer.to_string(), &mut list, &mut state);
+    }
+
+    if result == ActionResult::Ok {
+        result = storage::save(&path, &list, config.backups);
+    }
+
+    self::report(&result);
+    result.exit_code()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{C_ADD, C_DONE, C_EDIT, C_LIST, C_REMOVE};
+    use std::{env, fs};
+
+    fn args(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    // `text` without creation, completion and removal times, which change
+    // per run.
+    fn untimed(text: &str) -> String {
+        let times = regex::Regex::new(r" (?:created|completed|removed):[^ ;]+").unwrap();
+        times.replace_all(text, "").into_owned()
+    }
+
+    fn temp_path(name: &str) -> String {
+        let path = env::temp_dir().join(format!("todocli_cli_{name}.txt"));
+        let _ = fs::remove_file(&path);
+        path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn test_run() {
+        let path = temp_path("run");
+
+        assert_eq!(
+            run(
+                &args(&[C_ADD, "write report"]),
+                &path,
+                None,
+                &Config::default()
+            ),
+            0
+        );
+        assert_eq!(
+            run(
+                &args(&[C_ADD, "read", "book"]),
+                &path,
+                None,
+                &Config::default()
+            ),
+            0
+        );
+        assert_eq!(
+            run(&args(&[C_DONE, "1"]), &path, None, &Config::default()),
+            0
+        );
+        assert_eq!(run(&args(&[C_LIST]), &path, None, &Config::default()), 0);
+
+        let contents = untimed(&fs::read_to_string(&path).unwrap());
+        assert_eq!(contents, "1) #1 [+] write report;\n2) #2 [ ] read book;\n");
+
+        assert_eq!(
+            run(&args(&[C_REMOVE, "1"]), &path, None, &Config::default()),
+            0
+        );
+
+        let contents = untimed(&fs::read_to_string(&path).unwrap());
+        assert_eq!(
+            contents,
+            "1) #2 [ ] read book;\ntrash:\n1) #1 [+] write report at:0;\n"
+        );
+
+        assert_eq!(
+            run(
+                &args(&[C_EDIT, "#2", "read  two books"]),
+                &path,
+                None,
+                &Config::default()
+            ),
+            0
+        );
+
+        let contents = untimed(&fs::read_to_string(&path).unwrap());
+        assert_eq!(
+            contents,
+            "1) #2 [ ] read  two books;\ntrash:\n1) #1 [+] write report at:0;\n"
+        );
+
+        assert_eq!(
+            run(
+                &args(&[C_ADD, "don't", "forget"]),
+                &path,
+                None,
+                &Config::default()
+            ),
+            0
+        );
+
+        let contents = untimed(&fs::read_to_string(&path).unwrap());
+        assert_eq!(
+            contents,
+            "1) #2 [ ] read  two books;\n2) #3 [ ] don't forget;\ntrash:\n1) #1 [+] write report at:0;\n"
+        );
+
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_run_done_parent() {
+        let path = temp_path("run_done_parent");
+        fs::write(&path, "1) #1 [ ] trip;\n1.1) #2 [ ] pack;\n").unwrap();
+
+        assert_eq!(
+            run(&args(&[C_DONE, "1"]), &path, None, &Config::default()),
+            0
+        );
+
+        let contents = untimed(&fs::read_to_string(&path).unwrap());
+        assert_eq!(contents, "1) #1 [+] trip;\n  1.1) #2 [ ] pack;\n");
+
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_run_errors() {
+        let path = temp_path("run_errors");
+
+        assert_eq!(
+            run(&args(&[C_DONE, "1"]), &path, None, &Config::default()),
+            ActionResult::TaskNotFound.exit_code()
+        );
+        assert_eq!(
+            run(&args(&["unknown"]), &path, None, &Config::default()),
+            ActionResult::UnknownCommand.exit_code()
+        );
+        assert_eq!(
+            run(&args(&[C_ADD]), &path, None, &Config::default()),
+            ActionResult::NeedTask.exit_code()
+        );
+        assert_eq!(
+            run(&args(&[C_UNDO]), &path, None, &Config::default()),
+            ActionResult::InteractiveOnly(C_UNDO.to_string()).exit_code()
+        );
+        assert!(fs::metadata(&path).is_err());
+    }
+
+    #[test]
+    fn test_run_list() {
+        let path = temp_path("run_list");
+        let work = storage::list_path(&path, Some("work"));
+        fs::write(&work, "1) #1 [ ] report;\n").unwrap();
+
+        assert_eq!(
+            run(
+                &args(&[C_DONE, "1"]),
+                &path,
+                Some("work"),
+                &Config::default()
+            ),
+            0
+        );
+
+        let contents = untimed(&fs::read_to_string(&work).unwrap());
+        assert_eq!(contents, "1) #1 [+] report;\n");
+        assert!(fs::metadata(&path).is_err());
+
+        assert_eq!(
+            run(&args(&[C_LIST]), &path, Some("home"), &Config::default()),
+            ActionResult::ListNotFound.exit_code()
+        );
+        assert_eq!(
+            run(&args(&[C_USE, "work"]), &path, None, &Config::default()),
+            ActionResult::InteractiveOnly(C_USE.to_string()).exit_code()
+        );
+        assert_eq!(
+            run(
+                &args(&[C_ADD, "x"]),
+                &path,
+                Some("default"),
+                &Config::default()
+            ),
+            0
+        );
+        assert!(fs::metadata(&path).is_ok());
+
+        let _ = fs::remove_file(&path);
+        let _ = fs::remove_file(&work);
+    }
+
+    #[test]
+    fn test_run_limit() {
+        let path = temp_path("run_limit");
+        let config = Config {
+            max_tasks: Some(1),
+            ..Config::default()
+        };
+
+        assert_eq!(run(&args(&[C_ADD, "test1"]), &path, None, &config), 0);
+        assert_eq!(
+            run(&args(&[C_ADD, "test2"]), &path, None, &config),
+            ActionResult::ListFull.exit_code()
+        );
+
+        let _ = fs::remove_file(&path);
+    }
+}
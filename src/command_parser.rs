@@ -1,8 +1,123 @@
-use crate::config::{C_Y, C_YES};
+use crate::{
+    config::{C_ALL, C_N, C_NO, C_Y, C_YES, ID_PREFIX, PATH_SEPARATOR},
+    query,
+};
+use std::{fmt, str::FromStr};
 
-pub struct ParseResult<'a> {
-    pub command: &'a str,
-    pub arguments: Vec<&'a str>,
+const END_OF_OPTIONS: &str = "--";
+const OPTION_PREFIX: &str = "--";
+
+#[derive(Debug, PartialEq)]
+pub enum ParseError {
+    UnterminatedQuote(char),
+    DanglingEscape,
+    MissingArgument(&'static str),
+    UnexpectedArgument(String),
+    NotANumber(String),
+    NumberTooLarge(String),
+    ZeroIndex,
+    InvalidRange(String),
+    InvalidPriority(String),
+    InvalidDate(String),
+    InvalidRecurrence(String),
+    InvalidTag(String),
+    InvalidRegex(String),
+    UnknownOption(String),
+    InvalidSortKey(String),
+    InvalidQuery(query::Error),
+    InvalidViewName(String),
+    InvalidListName(String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::UnterminatedQuote(quote) => write!(f, "unterminated {quote} quote"),
+            ParseError::DanglingEscape => write!(f, "nothing to escape after \\"),
+            ParseError::MissingArgument(what) => write!(f, "missing {what}"),
+            ParseError::UnexpectedArgument(arg) => write!(f, "unexpected argument {arg:?}"),
+            ParseError::NotANumber(arg) => write!(f, "{arg:?} is not a number"),
+            ParseError::NumberTooLarge(arg) => write!(f, "{arg} is too large"),
+            ParseError::ZeroIndex => write!(f, "indices start at 1"),
+            ParseError::InvalidRange(arg) => write!(f, "{arg:?} is not a range like 2-6"),
+            ParseError::InvalidPriority(arg) => write!(f, "{arg:?} is not a priority"),
+            ParseError::InvalidDate(arg) => write!(f, "{arg:?} is not a date"),
+            ParseError::InvalidRecurrence(arg) => write!(
+                f,
+                "{arg:?} is not a recurrence (daily, weekly, weekly:mon,thu, monthly:15 or after:3d)"
+            ),
+            ParseError::InvalidTag(arg) => write!(f, "{arg:?} is not a +project or @context"),
+            ParseError::InvalidRegex(arg) => write!(f, "{arg:?} is not a valid regex"),
+            ParseError::UnknownOption(arg) => write!(f, "unknown option {arg}"),
+            ParseError::InvalidQuery(e) => write!(f, "{e}"),
+            ParseError::InvalidViewName(arg) => {
+                write!(f, "{arg:?} is not a view name (letters, digits, - and _)")
+            }
+            ParseError::InvalidListName(arg) => {
+                write!(f, "{arg:?} is not a list name (letters, digits, - and _)")
+            }
+            ParseError::InvalidSortKey(arg) => {
+                write!(f, "cannot sort by {arg:?} (text, created, due or priority)")
+            }
+        }
+    }
+}
+
+// A task as the user wrote it: a 1-based position, a dotted path of
+// positions down to a subtask (`3.2`) or a permanent id.
+#[derive(Debug, PartialEq, Clone)]
+pub enum TaskRef {
+    Position(usize),
+    Path(Vec<usize>),
+    Id(u64),
+}
+
+// One piece of a task selection: a single reference or a span of positions.
+#[derive(Debug, PartialEq)]
+pub enum Selected {
+    One(TaskRef),
+    Range(usize, usize),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Selection {
+    All,
+    Tasks(Vec<Selected>),
+}
+
+#[derive(Debug, Default)]
+pub struct ParseResult {
+    pub command: String,
+    pub arguments: Vec<String>,
+    // Arguments from this index on came after `--` and are never options.
+    pub literal_from: usize,
+    // Unquoted text from each argument to the end, spacing kept.
+    tails: Vec<String>,
+}
+
+impl ParseResult {
+    pub fn args(&self) -> Vec<&str> {
+        self.arguments.iter().map(String::as_str).collect()
+    }
+
+    // Whether the argument at `index` looks like `--name` and came before `--`.
+    pub fn is_option(&self, index: usize) -> bool {
+        index < self.literal_from
+            && self
+                .arguments
+                .get(index)
+                .is_some_and(|a| a.len() > 2 && a.starts_with(OPTION_PREFIX))
+    }
+
+    // Text following the command, quotes and escapes resolved.
+    pub fn rest(&self) -> &str {
+        self.rest_after(0)
+    }
+
+    // Text following the first `n` arguments with its spacing untouched.
+    pub fn rest_after(&self, n: usize) -> &str {
+        self.tails.get(n).map_or("", String::as_str)
+    }
 }
 
 pub fn is_confirm(target: &str) -> bool {
@@ -10,23 +125,221 @@ pub fn is_confirm(target: &str) -> bool {
     target == C_Y || target == C_YES
 }
 
-pub fn parse<'a>(target: &'a str) -> ParseResult<'a> {
-    let entries: Vec<&str> = target.split_whitespace().collect();
+pub fn is_deny(target: &str) -> bool {
+    let target = target.to_lowercase();
+    target == C_N || target == C_NO
+}
+
+// Any other backslash is kept as written, so paths like `C:\dir` survive.
+fn escape(text: &mut String, c: char) {
+    match c {
+        't' => text.push('\t'),
+        '\\' | '"' | '\'' | ' ' => text.push(c),
+        _ => {
+            text.push('\\');
+            text.push(c);
+        }
+    }
+}
+
+struct Word {
+    text: String,
+    start: usize,
+    end: usize,
+    quoted: bool,
+}
+
+// Splits a line shell-style: whitespace separates words, single quotes
+// keep everything literal, double quotes and bare words honour `\` escapes.
+// Quotes only open at the start of a word or right after another quoted
+// part, so the apostrophe in `don't` stays text. Along with the words it returns the unquoted line, spacing kept, that
+// the word spans point into.
+fn tokenize(target: &str) -> Result<(Vec<Word>, String), ParseError> {
+    let mut words = vec![];
+    let mut line = String::new();
+    let mut word: Option<Word> = None;
+    let mut after_quote = false;
+    let mut chars = target.chars();
 
-    if entries.len() > 0 {
-        return ParseResult {
-            command: match entries.get(0) {
-                Some(c) => c,
-                None => "",
-            },
-            arguments: entries[1..].to_vec(),
-        };
+    while let Some(c) = chars.next() {
+        let opens_quote = word.is_none() || after_quote;
+        after_quote = false;
+
+        if c.is_whitespace() {
+            if let Some(w) = word.take() {
+                words.push(w);
+            }
+            line.push(c);
+            continue;
+        }
+
+        let w = word.get_or_insert_with(|| Word {
+            text: String::new(),
+            start: line.len(),
+            end: line.len(),
+            quoted: false,
+        });
+
+        match c {
+            '\'' | '"' if opens_quote => {
+                w.quoted = true;
+                after_quote = true;
+
+                loop {
+                    let q = chars.next().ok_or(ParseError::UnterminatedQuote(c))?;
+
+                    if q == c {
+                        break;
+                    } else if q == '\\' && c == '"' {
+                        let e = chars.next().ok_or(ParseError::UnterminatedQuote(c))?;
+                        self::escape(&mut w.text, e);
+                    } else {
+                        w.text.push(q);
+                    }
+                }
+            }
+            '\\' => {
+                w.quoted = true;
+                let e = chars.next().ok_or(ParseError::DanglingEscape)?;
+                self::escape(&mut w.text, e);
+            }
+            _ => w.text.push(c),
+        }
+
+        line.truncate(w.start);
+        line.push_str(&w.text);
+        w.end = line.len();
     }
 
-    ParseResult {
-        command: "",
-        arguments: vec![],
+    if let Some(w) = word {
+        words.push(w);
     }
+
+    Ok((words, line))
+}
+
+// Digits only: signs, spaces and decimal points are all rejected.
+pub fn parse_number<T: FromStr>(target: &str) -> Result<T, ParseError> {
+    if target.is_empty() || !target.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(ParseError::NotANumber(target.to_string()));
+    }
+
+    target
+        .parse::<T>()
+        .map_err(|_| ParseError::NumberTooLarge(target.to_string()))
+}
+
+pub fn parse_position(target: &str) -> Result<usize, ParseError> {
+    match self::parse_number::<usize>(target)? {
+        0 => Err(ParseError::ZeroIndex),
+        position => Ok(position),
+    }
+}
+
+pub fn parse_task_ref(target: &str) -> Result<TaskRef, ParseError> {
+    match target.strip_prefix(ID_PREFIX) {
+        Some(id) => self::parse_number::<u64>(id).map(TaskRef::Id),
+        None if target.contains(PATH_SEPARATOR) => target
+            .split(PATH_SEPARATOR)
+            .map(self::parse_position)
+            .collect::<Result<Vec<usize>, ParseError>>()
+            .map(TaskRef::Path),
+        None => self::parse_position(target).map(TaskRef::Position),
+    }
+}
+
+fn parse_selected(piece: &str) -> Result<Selected, ParseError> {
+    match piece.split_once('-') {
+        Some((from, to)) => {
+            let (from, to) = (self::parse_position(from), self::parse_position(to));
+
+            match (from, to) {
+                (Ok(from), Ok(to)) if from <= to => Ok(Selected::Range(from, to)),
+                (Err(e @ ParseError::NumberTooLarge(..)), _)
+                | (_, Err(e @ ParseError::NumberTooLarge(..))) => Err(e),
+                _ => Err(ParseError::InvalidRange(piece.to_string())),
+            }
+        }
+        None if piece == C_ALL => Err(ParseError::UnexpectedArgument(piece.to_string())),
+        None => self::parse_task_ref(piece).map(Selected::One),
+    }
+}
+
+// Reads `all` or task references separated by spaces or commas, where
+// `N-M` stands for every position from N to M (`1 3`, `2-6`, `4,7-9`).
+pub fn parse_selection(args: &[&str]) -> Result<Selection, ParseError> {
+    match args {
+        [] => Err(ParseError::MissingArgument("task")),
+        [C_ALL] => Ok(Selection::All),
+        _ => args
+            .iter()
+            .flat_map(|arg| arg.split(','))
+            .map(self::parse_selected)
+            .collect::<Result<Vec<Selected>, ParseError>>()
+            .map(Selection::Tasks),
+    }
+}
+
+// Quotes a word so that `parse` reads it back unchanged. A lone `--` is
+// left bare so it still ends options.
+pub fn quote(word: &str) -> String {
+    if !word.is_empty() && !word.contains(|c: char| c.is_whitespace() || "'\"\\".contains(c)) {
+        return word.to_string();
+    }
+
+    let escaped = word.replace('\\', "\\\\").replace('"', "\\\"");
+    format!("\"{escaped}\"")
+}
+
+pub fn parse(target: &str) -> Result<ParseResult, ParseError> {
+    let (mut words, line) = self::tokenize(target)?;
+
+    if words.is_empty() {
+        return Ok(ParseResult::default());
+    }
+
+    let command = words.remove(0).text;
+
+    // Only a bare `--` ends options, a quoted one is an ordinary argument.
+    let marker = words
+        .iter()
+        .position(|w| !w.quoted && w.text == END_OF_OPTIONS);
+    let end = match (marker, words.len()) {
+        (Some(m), len) if m + 1 == len => words.get(m.wrapping_sub(1)).map_or(0, |w| w.end),
+        (_, _) => words.last().map_or(0, |w| w.end),
+    };
+
+    let tails = match marker {
+        Some(m) => {
+            // The marker and the spacing after it are left out of the text.
+            let (pause, resume) = match words.get(m + 1) {
+                Some(next) => (words[m].start, next.start),
+                None => (end, end),
+            };
+            let mut tails: Vec<String> = words[..m]
+                .iter()
+                .map(|w| format!("{}{}", &line[w.start..pause], &line[resume..end]))
+                .collect();
+            tails.extend(
+                words[m + 1..]
+                    .iter()
+                    .map(|w| line[w.start..end].to_string()),
+            );
+            words.remove(m);
+            tails
+        }
+        None => words
+            .iter()
+            .map(|w| line[w.start..end].to_string())
+            .collect(),
+    };
+
+    Ok(ParseResult {
+        command,
+        literal_from: marker.unwrap_or(words.len()),
+        arguments: words.into_iter().map(|w| w.text).collect(),
+        tails,
+    })
 }
 
 #[cfg(test)]
@@ -42,25 +355,269 @@ mod tests {
         assert!(is_confirm("YES"));
     }
 
+    #[test]
+    fn test_is_deny() {
+        assert!(is_deny("n"));
+        assert!(is_deny("No"));
+        assert!(!is_deny("y"));
+        assert!(!is_deny(""));
+    }
+
     #[test]
     fn test_parse_empty() {
-        let result = parse("");
-        assert!(result.command == "");
-        assert!(result.arguments.len() == 0);
+        let result = parse("").unwrap();
+        assert!(result.command.is_empty());
+        assert!(result.arguments.is_empty());
     }
 
     #[test]
     fn test_parse_command() {
         let command = "hello";
-        let result = parse(command);
+        let result = parse(command).unwrap();
         assert!(result.command == command);
     }
 
     #[test]
     fn test_parse_with_arguments() {
-        let arguments = vec!["a", "b"];
-        let result = parse("hello a b");
-        assert!(result.arguments.get(0) == arguments.get(0));
-        assert!(result.arguments.get(1) == arguments.get(1));
+        let arguments = ["a", "b"];
+        let result = parse("hello a b").unwrap();
+        assert!(result.args().first() == arguments.first());
+        assert!(result.args().get(1) == arguments.get(1));
+    }
+
+    #[test]
+    fn test_parse_rest() {
+        let result = parse("  add   buy  milk\tnow  \n").unwrap();
+        assert_eq!(result.command, "add");
+        assert_eq!(result.rest(), "buy  milk\tnow");
+
+        let result = parse("list").unwrap();
+        assert_eq!(result.rest(), "");
+    }
+
+    #[test]
+    fn test_parse_rest_after() {
+        let result = parse("edit 2   new  text\n").unwrap();
+        assert_eq!(result.rest_after(0), "2   new  text");
+        assert_eq!(result.rest_after(1), "new  text");
+        assert_eq!(result.rest_after(2), "text");
+        assert_eq!(result.rest_after(5), "");
+    }
+
+    #[test]
+    fn test_parse_quotes() {
+        let result = parse(r#"add "buy  milk" 'at\ the' shop"#).unwrap();
+        assert_eq!(result.args(), ["buy  milk", "at\\ the", "shop"]);
+        assert_eq!(result.rest(), "buy  milk at\\ the shop");
+
+        let result = parse(r#"add "say "'hi  there'"#).unwrap();
+        assert_eq!(result.args(), ["say hi  there"]);
+
+        let result = parse(r#"add don't forget 5" "milk""#).unwrap();
+        assert_eq!(result.args(), ["don't", "forget", "5\"", "milk"]);
+        assert_eq!(result.rest(), r#"don't forget 5" milk"#);
+
+        let result = parse(r#"add "" x"#).unwrap();
+        assert_eq!(result.args(), ["", "x"]);
+    }
+
+    #[test]
+    fn test_parse_escapes() {
+        let result = parse(r#"add a\ b \"c\" "d\"e" f\tg \\"#).unwrap();
+        assert_eq!(result.args(), ["a b", "\"c\"", "d\"e", "f\tg", "\\"]);
+        assert_eq!(result.rest(), "a b \"c\" d\"e f\tg \\");
+
+        let result = parse(r#"save C:\dir\x.txt "\d+""#).unwrap();
+        assert_eq!(result.args(), [r"C:\dir\x.txt", r"\d+"]);
+    }
+
+    #[test]
+    fn test_parse_end_of_options() {
+        let result = parse("list --done -- --pending -x").unwrap();
+        assert_eq!(result.args(), ["--done", "--pending", "-x"]);
+        assert_eq!(result.rest_after(1), "--pending -x");
+        assert_eq!(result.literal_from, 1);
+        assert!(result.is_option(0));
+        assert!(!result.is_option(1));
+        assert!(!result.is_option(2));
+
+        let result = parse("add -- -5 degrees").unwrap();
+        assert_eq!(result.rest(), "-5 degrees");
+
+        let result = parse("add a -- b").unwrap();
+        assert_eq!(result.rest(), "a b");
+        assert_eq!(result.rest_after(1), "b");
+
+        let result = parse(r#"add "--" -x"#).unwrap();
+        assert_eq!(result.args(), ["--", "-x"]);
+        assert_eq!(result.rest(), "-- -x");
+        assert!(!result.is_option(0));
+        assert!(!result.is_option(1));
+
+        let result = parse("add x --").unwrap();
+        assert_eq!(result.args(), ["x"]);
+        assert_eq!(result.rest(), "x");
+    }
+
+    #[test]
+    fn test_parse_number() {
+        assert_eq!(parse_number::<usize>("10"), Ok(10));
+        assert_eq!(parse_number::<usize>("007"), Ok(7));
+        assert_eq!(parse_number::<usize>("0"), Ok(0));
+
+        for invalid in ["", "a1", "10x", "1.5", "-1", "+1", " 1", "١"] {
+            assert_eq!(
+                parse_number::<usize>(invalid),
+                Err(ParseError::NotANumber(invalid.to_string()))
+            );
+        }
+
+        assert_eq!(
+            parse_number::<u64>("18446744073709551616"),
+            Err(ParseError::NumberTooLarge(
+                "18446744073709551616".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_task_ref() {
+        assert_eq!(parse_task_ref("1"), Ok(TaskRef::Position(1)));
+        assert_eq!(parse_task_ref("10"), Ok(TaskRef::Position(10)));
+        assert_eq!(parse_task_ref("#7"), Ok(TaskRef::Id(7)));
+        assert_eq!(parse_task_ref("3.2"), Ok(TaskRef::Path(vec![3, 2])));
+        assert_eq!(parse_task_ref("1.10.4"), Ok(TaskRef::Path(vec![1, 10, 4])));
+        assert_eq!(parse_task_ref("3.0"), Err(ParseError::ZeroIndex));
+        assert_eq!(
+            parse_task_ref("3."),
+            Err(ParseError::NotANumber(String::new()))
+        );
+        assert_eq!(
+            parse_task_ref("#3.2"),
+            Err(ParseError::NotANumber("3.2".to_string()))
+        );
+        assert_eq!(parse_task_ref("0"), Err(ParseError::ZeroIndex));
+        assert_eq!(
+            parse_task_ref("#"),
+            Err(ParseError::NotANumber(String::new()))
+        );
+        assert_eq!(
+            parse_task_ref("#x"),
+            Err(ParseError::NotANumber("x".to_string()))
+        );
+        assert_eq!(
+            parse_task_ref("##1"),
+            Err(ParseError::NotANumber("#1".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_selection() {
+        assert_eq!(parse_selection(&["all"]), Ok(Selection::All));
+        assert_eq!(
+            parse_selection(&["1", "#7", "3"]),
+            Ok(Selection::Tasks(vec![
+                Selected::One(TaskRef::Position(1)),
+                Selected::One(TaskRef::Id(7)),
+                Selected::One(TaskRef::Position(3)),
+            ]))
+        );
+        assert_eq!(
+            parse_selection(&["2-6"]),
+            Ok(Selection::Tasks(vec![Selected::Range(2, 6)]))
+        );
+        assert_eq!(
+            parse_selection(&["4,7-9", "11"]),
+            Ok(Selection::Tasks(vec![
+                Selected::One(TaskRef::Position(4)),
+                Selected::Range(7, 9),
+                Selected::One(TaskRef::Position(11)),
+            ]))
+        );
+        assert_eq!(
+            parse_selection(&["3.2,4"]),
+            Ok(Selection::Tasks(vec![
+                Selected::One(TaskRef::Path(vec![3, 2])),
+                Selected::One(TaskRef::Position(4)),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_parse_selection_invalid() {
+        let range = |r: &str| Err(ParseError::InvalidRange(r.to_string()));
+
+        assert_eq!(
+            parse_selection(&[]),
+            Err(ParseError::MissingArgument("task"))
+        );
+        assert_eq!(
+            parse_selection(&["all", "1"]),
+            Err(ParseError::UnexpectedArgument("all".to_string()))
+        );
+        assert_eq!(parse_selection(&["6-2"]), range("6-2"));
+        assert_eq!(parse_selection(&["0-2"]), range("0-2"));
+        assert_eq!(parse_selection(&["1-"]), range("1-"));
+        assert_eq!(parse_selection(&["-3"]), range("-3"));
+        assert_eq!(parse_selection(&["1-2-3"]), range("1-2-3"));
+        assert_eq!(parse_selection(&["a-b"]), range("a-b"));
+        assert_eq!(
+            parse_selection(&["4,"]),
+            Err(ParseError::NotANumber(String::new()))
+        );
+        assert_eq!(
+            parse_selection(&["1-99999999999999999999999"]),
+            Err(ParseError::NumberTooLarge(
+                "99999999999999999999999".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_quote() {
+        let words = [
+            "plain",
+            "",
+            "two  words",
+            "it's",
+            r#"say "hi""#,
+            r"C:\dir",
+            "a\tb",
+        ];
+
+        for word in words {
+            let result = parse(&format!("add {}", quote(word))).unwrap();
+            assert_eq!(result.args(), [word]);
+        }
+
+        assert_eq!(quote("plain"), "plain");
+        assert_eq!(quote("--"), "--");
+        assert_eq!(quote("it's"), r#""it's""#);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            parse(r#"add "buy milk"#).err(),
+            Some(ParseError::UnterminatedQuote('"'))
+        );
+        assert_eq!(
+            parse("add 'buy milk").err(),
+            Some(ParseError::UnterminatedQuote('\''))
+        );
+        assert_eq!(
+            parse(r#"add "a\"#).err(),
+            Some(ParseError::UnterminatedQuote('"'))
+        );
+        assert_eq!(parse("add milk\\").err(), Some(ParseError::DanglingEscape));
+        assert_eq!(
+            ParseError::UnterminatedQuote('"').to_string(),
+            "unterminated \" quote"
+        );
+        assert_eq!(ParseError::ZeroIndex.to_string(), "indices start at 1");
+        assert_eq!(
+            ParseError::NotANumber("a1".to_string()).to_string(),
+            "\"a1\" is not a number"
+        );
     }
 }
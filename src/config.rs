@@ -1,4 +1,8 @@
-pub const TASKS_LIST_MAX_CAPACITY: usize = 200;
+use crate::storage;
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
 
 pub const C_EXIT: &str = "exit";
 pub const C_HELP: &str = "help";
@@ -11,6 +15,320 @@ pub const C_UNDONE: &str = "undone";
 pub const C_CLEAR: &str = "clear";
 pub const C_SAVE: &str = "save";
 pub const C_LOAD: &str = "load";
+pub const C_UNDO: &str = "undo";
+pub const C_REDO: &str = "redo";
+pub const C_PRIO: &str = "prio";
+pub const C_DUE: &str = "due";
+pub const C_TODAY: &str = "today";
+pub const C_OVERDUE: &str = "overdue";
+pub const C_PROJECTS: &str = "projects";
+pub const C_CONTEXTS: &str = "contexts";
+pub const C_FIND: &str = "find";
+pub const C_BACKUPS: &str = "backups";
+pub const C_RESTORE: &str = "restore";
+pub const C_VIEW: &str = "view";
+pub const C_BLOCK: &str = "block";
+pub const C_UNBLOCK: &str = "unblock";
+pub const C_NEXT: &str = "next";
+pub const C_REPEAT: &str = "repeat";
+pub const C_START: &str = "start";
+pub const C_WAIT: &str = "wait";
+pub const C_CANCEL: &str = "cancel";
+pub const C_REOPEN: &str = "reopen";
+pub const C_ARCHIVE: &str = "archive";
+pub const C_UNARCHIVE: &str = "unarchive";
+pub const C_SEARCH: &str = "search";
+pub const C_TRASH: &str = "trash";
+pub const C_EMPTY: &str = "empty";
+pub const C_USE: &str = "use";
+pub const C_LISTS: &str = "lists";
+pub const C_CREATE: &str = "create";
+pub const C_DELETE: &str = "delete";
+pub const C_MOVE: &str = "move";
+pub const C_TO: &str = "to";
 
 pub const C_Y: &str = "y";
 pub const C_YES: &str = "yes";
+pub const C_N: &str = "n";
+pub const C_NO: &str = "no";
+pub const C_NONE: &str = "none";
+pub const C_ALL: &str = "all";
+pub const C_ON: &str = "on";
+
+pub const O_PENDING: &str = "--pending";
+pub const O_DONE: &str = "--done";
+pub const O_SORT: &str = "--sort";
+pub const O_REVERSE: &str = "--reverse";
+pub const O_LIMIT: &str = "--limit";
+pub const O_LONG: &str = "--long";
+pub const O_UNDER: &str = "--under";
+pub const O_OLDER: &str = "--older";
+
+pub const ID_PREFIX: char = '#';
+pub const PATH_SEPARATOR: char = '.';
+
+pub const DATA_DIR_NAME: &str = "todocli";
+pub const DATA_FILE_NAME: &str = "list.json";
+pub const DEFAULT_LIST_NAME: &str = "default";
+pub const ENV_FILE: &str = "TODOCLI_FILE";
+pub const ENV_LIST: &str = "TODOCLI_LIST";
+pub const DEFAULT_CONFIG_NAME: &str = "todocli.conf";
+pub const ENV_CONFIG: &str = "TODOCLI_CONFIG";
+
+pub const K_MAX_TASKS: &str = "max_tasks";
+pub const K_HISTORY_DEPTH: &str = "history_depth";
+pub const K_BACKUPS: &str = "backups";
+pub const K_TRASH_SIZE: &str = "trash_size";
+pub const K_VIEW_PREFIX: &str = "view.";
+
+pub const DEFAULT_HISTORY_DEPTH: usize = 100;
+pub const DEFAULT_BACKUPS: usize = 5;
+pub const DEFAULT_TRASH_SIZE: usize = 50;
+
+// `$XDG_CONFIG_HOME/todocli/todocli.conf`, falling back to
+// `~/.config/todocli/todocli.conf` and then to the current directory.
+fn resolve_config_file(xdg_config_home: Option<String>, home: Option<String>) -> PathBuf {
+    let base = match (xdg_config_home, home) {
+        (Some(xdg), _) if !xdg.is_empty() => PathBuf::from(xdg),
+        (_, Some(home)) if !home.is_empty() => Path::new(&home).join(".config"),
+        _ => PathBuf::from("."),
+    };
+
+    base.join(DATA_DIR_NAME).join(DEFAULT_CONFIG_NAME)
+}
+
+pub fn config_path() -> String {
+    match env::var(ENV_CONFIG) {
+        Ok(path) => path,
+        Err(..) => resolve_config_file(env::var("XDG_CONFIG_HOME").ok(), env::var("HOME").ok())
+            .to_string_lossy()
+            .to_string(),
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Config {
+    pub max_tasks: Option<usize>,
+    pub history_depth: usize,
+    pub backups: usize,
+    pub trash_size: usize,
+    // Named queries as `(name, expression)`, in file order.
+    pub views: Vec<(String, String)>,
+    // Where the config was loaded from, so views can be written back.
+    pub path: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            max_tasks: None,
+            history_depth: DEFAULT_HISTORY_DEPTH,
+            backups: DEFAULT_BACKUPS,
+            trash_size: DEFAULT_TRASH_SIZE,
+            views: vec![],
+            path: None,
+        }
+    }
+}
+
+impl Config {
+    // Reads `key = value` lines, skipping blank lines, `#` comments,
+    // unknown keys and values that cannot be parsed.
+    pub fn parse(text: &str) -> Self {
+        let mut config = Config::default();
+
+        text.lines()
+            .map(|l| l.trim())
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+            .filter_map(|l| l.split_once('='))
+            .for_each(|(key, value)| {
+                let value = value.trim();
+
+                match key.trim() {
+                    K_MAX_TASKS => {
+                        config.max_tasks = value.parse::<usize>().ok().filter(|v| *v > 0);
+                    }
+                    K_HISTORY_DEPTH => {
+                        if let Ok(depth) = value.parse::<usize>() {
+                            config.history_depth = depth;
+                        }
+                    }
+                    K_BACKUPS => {
+                        if let Ok(backups) = value.parse::<usize>() {
+                            config.backups = backups;
+                        }
+                    }
+                    K_TRASH_SIZE => {
+                        if let Ok(size) = value.parse::<usize>() {
+                            config.trash_size = size;
+                        }
+                    }
+                    key => {
+                        if let Some(name) = key.strip_prefix(K_VIEW_PREFIX) {
+                            config.views.retain(|(n, _)| n != name);
+                            config.views.push((name.to_string(), value.to_string()));
+                        }
+                    }
+                }
+            });
+
+        config
+    }
+
+    pub fn load(path: &str) -> Self {
+        let config = match fs::read_to_string(path) {
+            Ok(contents) => Config::parse(&contents),
+            Err(..) => Config::default(),
+        };
+
+        Config {
+            path: Some(path.to_string()),
+            ..config
+        }
+    }
+}
+
+// Sets or, with no expression, removes the view `name` in the config file
+// at `path`, leaving every other line as it was.
+pub fn write_view(path: &str, name: &str, expression: Option<&str>) -> io::Result<()> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e),
+    };
+
+    let key = format!("{K_VIEW_PREFIX}{name}");
+    let mut lines: Vec<String> = contents
+        .lines()
+        .filter(|l| l.split_once('=').map(|(k, _)| k.trim()) != Some(key.as_str()))
+        .map(|l| l.to_string())
+        .collect();
+
+    if let Some(expression) = expression {
+        lines.push(format!("{key} = {expression}"));
+    }
+
+    let mut contents = lines.join("\n");
+    contents.push('\n');
+    storage::write_atomic(path, contents.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_default() {
+        assert_eq!(Config::parse(""), Config::default());
+        assert!(Config::parse("").max_tasks.is_none());
+    }
+
+    #[test]
+    fn test_parse_max_tasks() {
+        let config = Config::parse("# limits\n\nmax_tasks = 50\nunknown = 1\n");
+        assert_eq!(config.max_tasks, Some(50));
+    }
+
+    #[test]
+    fn test_parse_history_depth() {
+        assert_eq!(Config::parse("").history_depth, DEFAULT_HISTORY_DEPTH);
+        assert_eq!(Config::parse("history_depth = 5").history_depth, 5);
+        assert_eq!(Config::parse("history_depth = 0").history_depth, 0);
+        assert_eq!(
+            Config::parse("history_depth = -1").history_depth,
+            DEFAULT_HISTORY_DEPTH
+        );
+    }
+
+    #[test]
+    fn test_parse_backups() {
+        assert_eq!(Config::parse("").backups, DEFAULT_BACKUPS);
+        assert_eq!(Config::parse("backups = 0").backups, 0);
+        assert_eq!(Config::parse("backups = 10").backups, 10);
+    }
+
+    #[test]
+    fn test_parse_trash_size() {
+        assert_eq!(Config::parse("").trash_size, DEFAULT_TRASH_SIZE);
+        assert_eq!(Config::parse("trash_size = 0").trash_size, 0);
+        assert_eq!(Config::parse("trash_size = 10").trash_size, 10);
+        assert_eq!(
+            Config::parse("trash_size = all").trash_size,
+            DEFAULT_TRASH_SIZE
+        );
+    }
+
+    #[test]
+    fn test_parse_max_tasks_invalid() {
+        assert!(Config::parse("max_tasks = many").max_tasks.is_none());
+        assert!(Config::parse("max_tasks = 0").max_tasks.is_none());
+        assert!(Config::parse("max_tasks").max_tasks.is_none());
+    }
+
+    #[test]
+    fn test_parse_views() {
+        let config = Config::parse(
+            "view.weekly = status:pending and due<+7d\nview.work = tag:work\nview.weekly = status:done\n",
+        );
+
+        assert_eq!(
+            config.views,
+            vec![
+                ("work".to_string(), "tag:work".to_string()),
+                ("weekly".to_string(), "status:done".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_resolve_config_file() {
+        assert_eq!(
+            resolve_config_file(Some("/xdg".to_string()), Some("/home/u".to_string())),
+            PathBuf::from("/xdg/todocli/todocli.conf")
+        );
+        assert_eq!(
+            resolve_config_file(Some("".to_string()), Some("/home/u".to_string())),
+            PathBuf::from("/home/u/.config/todocli/todocli.conf")
+        );
+        assert_eq!(
+            resolve_config_file(None, None),
+            PathBuf::from("./todocli/todocli.conf")
+        );
+    }
+
+    #[test]
+    fn test_write_view() {
+        let dir = env::temp_dir().join("todocli_test_write_view");
+        let _ = fs::remove_dir_all(&dir);
+        let path = dir.join("todocli").join("todocli.conf");
+        let path = path.to_string_lossy().to_string();
+
+        assert!(write_view(&path, "work", Some("tag:work")).is_ok());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "view.work = tag:work\n");
+        assert!(fs::metadata(format!("{path}.tmp")).is_err());
+
+        fs::write(&path, "# limits\nmax_tasks = 5\nview.work = tag:work\n").unwrap();
+
+        assert!(write_view(&path, "late", Some("due<today")).is_ok());
+        assert!(write_view(&path, "work", Some("tag:work or tag:office")).is_ok());
+
+        let config = Config::load(&path);
+        assert_eq!(config.max_tasks, Some(5));
+        assert_eq!(config.path, Some(path.clone()));
+        assert_eq!(
+            config.views,
+            vec![
+                ("late".to_string(), "due<today".to_string()),
+                ("work".to_string(), "tag:work or tag:office".to_string()),
+            ]
+        );
+
+        assert!(write_view(&path, "late", None).is_ok());
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "# limits\nmax_tasks = 5\nview.work = tag:work or tag:office\n"
+        );
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,527 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt,
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+const SECONDS_PER_DAY: u64 = 86_400;
+const SECONDS_PER_HOUR: u64 = 3_600;
+const SECONDS_PER_MINUTE: u64 = 60;
+
+// Dates are written with four-digit years, so arithmetic stays within them.
+const MIN_YEAR: i64 = 0;
+const MAX_YEAR: i64 = 9999;
+
+const WEEKDAYS: [[&str; 4]; 7] = [
+    ["mon", "monday", "", ""],
+    ["tue", "tues", "tuesday", ""],
+    ["wed", "wednesday", "", ""],
+    ["thu", "thur", "thurs", "thursday"],
+    ["fri", "friday", "", ""],
+    ["sat", "saturday", "", ""],
+    ["sun", "sunday", "", ""],
+];
+
+// Calendar day stored as the number of days since 1970-01-01.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub struct Date {
+    days: i64,
+}
+
+fn is_leap_year(year: i64) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        _ => match is_leap_year(year) {
+            true => 29,
+            false => 28,
+        },
+    }
+}
+
+impl Date {
+    pub fn from_ymd(year: i64, month: u32, day: u32) -> Option<Self> {
+        if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+            return None;
+        }
+
+        // Days-from-civil conversion over 400-year eras, see
+        // http://howardhinnant.github.io/date_algorithms.html
+        let y = if month <= 2 { year - 1 } else { year };
+        let era = y.div_euclid(400);
+        let yoe = y.rem_euclid(400);
+        let m = month as i64;
+        let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + day as i64 - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+
+        Some(Self {
+            days: era * 146_097 + doe - 719_468,
+        })
+    }
+
+    pub fn from_timestamp(seconds: u64) -> Self {
+        Self {
+            days: (seconds / SECONDS_PER_DAY) as i64,
+        }
+    }
+
+    pub fn ymd(&self) -> (i64, u32, u32) {
+        let z = self.days + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z.rem_euclid(146_097);
+        let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+        (year, month, day)
+    }
+
+    // Monday is 0, Sunday is 6.
+    pub fn weekday(&self) -> u32 {
+        (self.days + 3).rem_euclid(7) as u32
+    }
+
+    fn in_range(self) -> Option<Self> {
+        (MIN_YEAR..=MAX_YEAR)
+            .contains(&self.ymd().0)
+            .then_some(self)
+    }
+
+    // `None` past the years dates can be written with.
+    pub fn add_days(&self, days: i64) -> Option<Self> {
+        let days = self.days.checked_add(days)?;
+
+        // Far enough out of range that `ymd` cannot overflow either.
+        if days.unsigned_abs() > 10_000 * 366 {
+            return None;
+        }
+
+        Self { days }.in_range()
+    }
+
+    // Clamps the day to the length of the target month, so Jan 31
+    // plus one month is the last day of February.
+    pub fn add_months(&self, months: i64) -> Option<Self> {
+        let (year, month, day) = self.ymd();
+        let total = (year * 12 + (month as i64 - 1)).checked_add(months)?;
+        let year = total.div_euclid(12);
+        let month = total.rem_euclid(12) as u32 + 1;
+
+        if !(MIN_YEAR..=MAX_YEAR).contains(&year) {
+            return None;
+        }
+
+        Self::from_ymd(year, month, day.min(days_in_month(year, month)))
+    }
+
+    // The same month on `day`, clamped to the length of the month.
+    pub fn on_day(&self, day: u32) -> Self {
+        let (year, month, _) = self.ymd();
+        let day = day.clamp(1, days_in_month(year, month));
+
+        Self::from_ymd(year, month, day).expect("day is clamped to the month")
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (year, month, day) = self.ymd();
+        write!(f, "{year:04}-{month:02}-{day:02}")
+    }
+}
+
+impl FromStr for Date {
+    type Err = ();
+
+    // Strict `YYYY-MM-DD`, as used by the storage formats.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.split('-').collect();
+
+        match parts.as_slice() {
+            [year, month, day]
+                if year.len() == 4
+                    && month.len() == 2
+                    && day.len() == 2
+                    && parts.iter().all(|p| p.bytes().all(|b| b.is_ascii_digit())) =>
+            {
+                let year = year.parse::<i64>().map_err(|_| ())?;
+                let month = month.parse::<u32>().map_err(|_| ())?;
+                let day = day.parse::<u32>().map_err(|_| ())?;
+
+                Date::from_ymd(year, month, day).ok_or(())
+            }
+            _ => Err(()),
+        }
+    }
+}
+
+impl From<Date> for String {
+    fn from(date: Date) -> Self {
+        date.to_string()
+    }
+}
+
+impl TryFrom<String> for Date {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value
+            .parse::<Date>()
+            .map_err(|_| format!("invalid date: {value}"))
+    }
+}
+
+// A moment in UTC with second precision, written as `2026-10-18T14:03:05Z`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub struct Timestamp {
+    seconds: u64,
+}
+
+impl Timestamp {
+    pub fn from_seconds(seconds: u64) -> Self {
+        Self { seconds }
+    }
+
+    pub fn now() -> Self {
+        Self::from_seconds(now_millis() / 1000)
+    }
+
+    pub fn date(&self) -> Date {
+        Date::from_timestamp(self.seconds)
+    }
+}
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let time = self.seconds % SECONDS_PER_DAY;
+
+        write!(
+            f,
+            "{}T{:02}:{:02}:{:02}Z",
+            self.date(),
+            time / SECONDS_PER_HOUR,
+            time % SECONDS_PER_HOUR / SECONDS_PER_MINUTE,
+            time % SECONDS_PER_MINUTE
+        )
+    }
+}
+
+impl FromStr for Timestamp {
+    type Err = ();
+
+    // Strict `YYYY-MM-DDTHH:MM:SSZ`, as written by `Display`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (date, time) = s.split_once('T').ok_or(())?;
+        let date = date.parse::<Date>()?;
+        let parts: Vec<&str> = time.strip_suffix('Z').ok_or(())?.split(':').collect();
+
+        let [hours, minutes, seconds] = parts.as_slice() else {
+            return Err(());
+        };
+
+        let field = |text: &str, limit: u64| match text.parse::<u64>() {
+            Ok(value) if text.len() == 2 && text.bytes().all(|b| b.is_ascii_digit()) => {
+                Some(value).filter(|v| *v < limit)
+            }
+            _ => None,
+        };
+
+        let time = field(hours, 24).ok_or(())? * SECONDS_PER_HOUR
+            + field(minutes, 60).ok_or(())? * SECONDS_PER_MINUTE
+            + field(seconds, 60).ok_or(())?;
+
+        let days = u64::try_from(date.days).map_err(|_| ())?;
+        Ok(Self::from_seconds(days * SECONDS_PER_DAY + time))
+    }
+}
+
+impl From<Timestamp> for String {
+    fn from(timestamp: Timestamp) -> Self {
+        timestamp.to_string()
+    }
+}
+
+impl TryFrom<String> for Timestamp {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value
+            .parse::<Timestamp>()
+            .map_err(|_| format!("invalid timestamp: {value}"))
+    }
+}
+
+pub fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+// The current day in UTC, like every timestamp the list keeps. Without a
+// time zone database the local day cannot be told reliably, so `today`
+// and `overdue` turn over at midnight UTC.
+pub fn today() -> Date {
+    Date::from_timestamp(now_millis() / 1000)
+}
+
+pub fn weekday_name(weekday: u32) -> &'static str {
+    WEEKDAYS[weekday as usize % 7][0]
+}
+
+pub fn parse_weekday(input: &str) -> Option<u32> {
+    WEEKDAYS
+        .iter()
+        .position(|names| names.iter().any(|n| !n.is_empty() && *n == input))
+        .map(|p| p as u32)
+}
+
+fn parse_offset(amount: &str, unit: &str, today: Date) -> Option<Date> {
+    let amount = amount.parse::<i64>().ok()?;
+
+    match unit {
+        "d" | "day" | "days" => today.add_days(amount),
+        "w" | "week" | "weeks" => today.add_days(amount.checked_mul(7)?),
+        "m" | "month" | "months" => today.add_months(amount),
+        _ => None,
+    }
+}
+
+// Resolves a human date relative to `today`. Accepts ISO dates,
+// `today`/`tomorrow`/`yesterday`, weekday names (optionally prefixed by
+// `next`, always strictly after today), `next week`/`next month`,
+// `+3d`/`+2w`/`+1m` and `in 3 days`.
+pub fn parse(input: &str, today: Date) -> Option<Date> {
+    let input = input.trim().to_lowercase();
+    let words: Vec<&str> = input.split_whitespace().collect();
+
+    match words.as_slice() {
+        ["today"] | ["tod"] => Some(today),
+        ["tomorrow"] | ["tom"] => today.add_days(1),
+        ["yesterday"] => today.add_days(-1),
+        ["next", "week"] => today.add_days(7),
+        ["next", "month"] => today.add_months(1),
+        ["in", amount, unit] => parse_offset(amount, unit, today),
+        [word] | ["next", word] if parse_weekday(word).is_some() => {
+            let target = parse_weekday(word)? as i64;
+            let current = today.weekday() as i64;
+            let ahead = (target - current - 1).rem_euclid(7) + 1;
+
+            today.add_days(ahead)
+        }
+        [word] if word.starts_with('+') => {
+            let offset = &word[1..];
+            let split = offset.find(|c: char| !c.is_ascii_digit())?;
+
+            parse_offset(&offset[..split], &offset[split..], today)
+        }
+        [word] => word.parse::<Date>().ok(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i64, month: u32, day: u32) -> Date {
+        Date::from_ymd(year, month, day).unwrap()
+    }
+
+    // Saturday
+    fn base() -> Date {
+        date(2026, 10, 17)
+    }
+
+    #[test]
+    fn test_epoch() {
+        assert_eq!(date(1970, 1, 1).days, 0);
+        assert_eq!(date(1970, 1, 1).weekday(), 3);
+        assert_eq!(date(1969, 12, 31).days, -1);
+    }
+
+    #[test]
+    fn test_ymd_round_trip() {
+        for days in -800_000..800_000 {
+            if days % 97 != 0 {
+                continue;
+            }
+
+            let d = Date { days };
+            let (year, month, day) = d.ymd();
+            assert_eq!(Date::from_ymd(year, month, day), Some(d));
+        }
+    }
+
+    #[test]
+    fn test_from_ymd_invalid() {
+        assert!(Date::from_ymd(2026, 0, 1).is_none());
+        assert!(Date::from_ymd(2026, 13, 1).is_none());
+        assert!(Date::from_ymd(2026, 4, 31).is_none());
+        assert!(Date::from_ymd(2026, 2, 29).is_none());
+        assert!(Date::from_ymd(2024, 2, 29).is_some());
+        assert!(Date::from_ymd(1900, 2, 29).is_none());
+        assert!(Date::from_ymd(2000, 2, 29).is_some());
+    }
+
+    #[test]
+    fn test_weekday() {
+        assert_eq!(base().weekday(), 5);
+        assert_eq!(date(2026, 10, 19).weekday(), 0);
+        assert_eq!(date(2000, 1, 1).weekday(), 5);
+    }
+
+    #[test]
+    fn test_add_months() {
+        assert_eq!(date(2026, 1, 31).add_months(1), Some(date(2026, 2, 28)));
+        assert_eq!(date(2024, 1, 31).add_months(1), Some(date(2024, 2, 29)));
+        assert_eq!(date(2026, 11, 15).add_months(2), Some(date(2027, 1, 15)));
+        assert_eq!(date(2026, 3, 31).add_months(-1), Some(date(2026, 2, 28)));
+        assert_eq!(date(9999, 12, 1).add_months(1), None);
+        assert_eq!(date(2026, 1, 1).add_months(i64::MAX), None);
+    }
+
+    #[test]
+    fn test_add_days_overflow() {
+        assert_eq!(date(9999, 12, 30).add_days(1), Some(date(9999, 12, 31)));
+        assert_eq!(date(9999, 12, 31).add_days(1), None);
+        assert_eq!(date(0, 1, 1).add_days(-1), None);
+        assert_eq!(base().add_days(i64::MAX), None);
+        assert_eq!(base().add_days(i64::MIN), None);
+
+        assert_eq!(parse("+9223372036854775807d", base()), None);
+        assert_eq!(parse("+9223372036854775807w", base()), None);
+        assert_eq!(parse("in 9223372036854775807 months", base()), None);
+        assert_eq!(parse("+9999999d", base()), None);
+    }
+
+    #[test]
+    fn test_on_day() {
+        assert_eq!(date(2026, 2, 10).on_day(15), date(2026, 2, 15));
+        assert_eq!(date(2026, 2, 10).on_day(31), date(2026, 2, 28));
+        assert_eq!(date(2026, 4, 10).on_day(0), date(2026, 4, 1));
+    }
+
+    #[test]
+    fn test_display_and_from_str() {
+        assert_eq!(date(2026, 11, 1).to_string(), "2026-11-01");
+        assert_eq!("2026-11-01".parse::<Date>(), Ok(date(2026, 11, 1)));
+        assert!("2026-11-1".parse::<Date>().is_err());
+        assert!("2026-02-30".parse::<Date>().is_err());
+        assert!("2026-+1-01".parse::<Date>().is_err());
+        assert!("tomorrow".parse::<Date>().is_err());
+    }
+
+    #[test]
+    fn test_timestamp() {
+        let timestamp = Timestamp::from_seconds(1_792_332_185);
+
+        assert_eq!(timestamp.to_string(), "2026-10-18T14:03:05Z");
+        assert_eq!(timestamp.date(), date(2026, 10, 18));
+        assert_eq!("2026-10-18T14:03:05Z".parse::<Timestamp>(), Ok(timestamp));
+        assert_eq!(
+            "1970-01-01T00:00:00Z".parse::<Timestamp>(),
+            Ok(Timestamp::from_seconds(0))
+        );
+
+        for invalid in [
+            "2026-10-18",
+            "2026-10-18T14:03:05",
+            "2026-10-18T24:00:00Z",
+            "2026-10-18T14:60:00Z",
+            "2026-10-18T14:3:05Z",
+            "2026-10-18T14:03Z",
+            "1969-12-31T23:59:59Z",
+        ] {
+            assert!(invalid.parse::<Timestamp>().is_err(), "{invalid:?}");
+        }
+    }
+
+    #[test]
+    fn test_timestamp_serde() {
+        let timestamp = Timestamp::from_seconds(1_792_332_185);
+        let json = serde_json::to_string(&timestamp).unwrap();
+
+        assert_eq!(json, "\"2026-10-18T14:03:05Z\"");
+        assert_eq!(serde_json::from_str::<Timestamp>(&json).unwrap(), timestamp);
+    }
+
+    #[test]
+    fn test_serde() {
+        let json = serde_json::to_string(&date(2026, 11, 1)).unwrap();
+        assert_eq!(json, "\"2026-11-01\"");
+        assert_eq!(
+            serde_json::from_str::<Date>(&json).unwrap(),
+            date(2026, 11, 1)
+        );
+        assert!(serde_json::from_str::<Date>("\"soon\"").is_err());
+    }
+
+    #[test]
+    fn test_from_timestamp() {
+        assert_eq!(Date::from_timestamp(0), date(1970, 1, 1));
+        assert_eq!(Date::from_timestamp(86_399), date(1970, 1, 1));
+        assert_eq!(Date::from_timestamp(1_792_281_600), date(2026, 10, 18));
+    }
+
+    #[test]
+    fn test_parse_keywords() {
+        assert_eq!(parse("today", base()), Some(base()));
+        assert_eq!(parse("Tomorrow", base()), Some(date(2026, 10, 18)));
+        assert_eq!(parse("yesterday", base()), Some(date(2026, 10, 16)));
+        assert_eq!(parse("next week", base()), Some(date(2026, 10, 24)));
+        assert_eq!(parse("next month", base()), Some(date(2026, 11, 17)));
+    }
+
+    #[test]
+    fn test_parse_iso() {
+        assert_eq!(parse("2026-11-01", base()), Some(date(2026, 11, 1)));
+        assert_eq!(parse(" 2026-11-01 ", base()), Some(date(2026, 11, 1)));
+        assert_eq!(parse("2026-11-31", base()), None);
+    }
+
+    #[test]
+    fn test_parse_weekdays() {
+        assert_eq!(parse("fri", base()), Some(date(2026, 10, 23)));
+        assert_eq!(parse("next fri", base()), Some(date(2026, 10, 23)));
+        assert_eq!(parse("friday", base()), Some(date(2026, 10, 23)));
+        assert_eq!(parse("mon", base()), Some(date(2026, 10, 19)));
+        assert_eq!(parse("sun", base()), Some(date(2026, 10, 18)));
+        assert_eq!(parse("sat", base()), Some(date(2026, 10, 24)));
+        assert_eq!(parse("thurs", base()), Some(date(2026, 10, 22)));
+    }
+
+    #[test]
+    fn test_parse_offsets() {
+        assert_eq!(parse("+3d", base()), Some(date(2026, 10, 20)));
+        assert_eq!(parse("+2w", base()), Some(date(2026, 10, 31)));
+        assert_eq!(parse("+1m", base()), Some(date(2026, 11, 17)));
+        assert_eq!(parse("in 3 days", base()), Some(date(2026, 10, 20)));
+        assert_eq!(parse("in 1 week", base()), Some(date(2026, 10, 24)));
+        assert_eq!(parse("in 2 months", base()), Some(date(2026, 12, 17)));
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert_eq!(parse("", base()), None);
+        assert_eq!(parse("soon", base()), None);
+        assert_eq!(parse("next", base()), None);
+        assert_eq!(parse("next year", base()), None);
+        assert_eq!(parse("+", base()), None);
+        assert_eq!(parse("+3", base()), None);
+        assert_eq!(parse("+d", base()), None);
+        assert_eq!(parse("+3y", base()), None);
+        assert_eq!(parse("in three days", base()), None);
+        assert_eq!(parse("in 3 fortnights", base()), None);
+    }
+}
@@ -0,0 +1,212 @@
+// Property tests over random input. Cases come from a small seeded
+// generator so that any failure reproduces on every run.
+
+use crate::{
+    actions::{self, ActionResult},
+    command_parser::{self, ParseError, TaskRef},
+    models::{list::List, state::State},
+};
+
+const CASES: usize = 2000;
+
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        // xorshift64*
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len())]
+    }
+
+    fn string(&mut self, alphabet: &[char], max_len: usize) -> String {
+        let len = self.below(max_len + 1);
+        (0..len).map(|_| *self.pick(alphabet)).collect()
+    }
+}
+
+const NUMERIC: &[char] = &[
+    '0', '1', '2', '9', '#', '-', ',', '.', '+', 'a', 'x', ' ', '١', 'é',
+];
+const LINE: &[char] = &[
+    'a', 'b', '1', '2', '0', ' ', '\t', '"', '\'', '\\', '-', ',', '#', '+', '@', ';', '[', ']',
+    'é', '\n',
+];
+// Kept on a few lines, rustfmt would give every entry its own.
+#[rustfmt::skip]
+const COMMANDS: &[&str] = &[
+    "add", "edit", "remove", "done", "undone", "prio", "due", "list", "today", "overdue",
+    "projects", "contexts", "start", "wait", "cancel", "reopen", "archive", "unarchive",
+    "trash", "restore", "clear", "undo", "redo", "backups", "lists", "use", "move", "help", "y", "n", "",
+];
+
+#[test]
+fn test_task_ref_matches_reference_model() {
+    let mut rng = Rng(0x5eed_0001);
+
+    for _ in 0..CASES {
+        let input = rng.string(NUMERIC, 24);
+
+        let (digits, as_id) = match input.strip_prefix('#') {
+            Some(rest) => (rest, true),
+            None => (input.as_str(), false),
+        };
+        let is_number = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+        let is_path = !as_id && digits.contains('.');
+        let all_digits = match is_path {
+            true => digits.split('.').all(is_number),
+            false => is_number(digits),
+        };
+
+        match command_parser::parse_task_ref(&input) {
+            Ok(TaskRef::Id(id)) => {
+                assert!(as_id && all_digits, "{input:?}");
+                assert_eq!(digits.parse::<u64>(), Ok(id));
+            }
+            Ok(TaskRef::Position(position)) => {
+                assert!(
+                    !as_id && !is_path && all_digits && position > 0,
+                    "{input:?}"
+                );
+                assert_eq!(digits.parse::<usize>(), Ok(position));
+            }
+            Ok(TaskRef::Path(path)) => {
+                assert!(
+                    is_path && all_digits && path.iter().all(|p| *p > 0),
+                    "{input:?}"
+                );
+                let pieces: Vec<usize> = digits.split('.').map(|p| p.parse().unwrap()).collect();
+                assert_eq!(pieces, path);
+            }
+            Err(ParseError::NotANumber(..)) => assert!(!all_digits, "{input:?}"),
+            Err(ParseError::NumberTooLarge(piece)) => {
+                assert!(is_number(&piece) && input.contains(&piece), "{input:?}")
+            }
+            Err(ParseError::ZeroIndex) => {
+                assert!(
+                    !as_id
+                        && digits
+                            .split('.')
+                            .any(|p| is_number(p) && p.bytes().all(|b| b == b'0')),
+                    "{input:?}"
+                )
+            }
+            Err(e) => panic!("unexpected {e:?} for {input:?}"),
+        }
+    }
+}
+
+#[test]
+fn test_task_ref_round_trip() {
+    let mut rng = Rng(0x5eed_0002);
+
+    for _ in 0..CASES {
+        let n = rng.next() >> rng.below(64);
+
+        assert_eq!(
+            command_parser::parse_task_ref(&format!("#{n}")),
+            Ok(TaskRef::Id(n))
+        );
+
+        match usize::try_from(n) {
+            Ok(0) => {
+                assert_eq!(
+                    command_parser::parse_task_ref("0"),
+                    Err(ParseError::ZeroIndex)
+                )
+            }
+            Ok(position) => assert_eq!(
+                command_parser::parse_task_ref(&n.to_string()),
+                Ok(TaskRef::Position(position))
+            ),
+            Err(..) => (),
+        }
+    }
+}
+
+#[test]
+fn test_selection_ranges_are_ordered() {
+    let mut rng = Rng(0x5eed_0003);
+
+    for _ in 0..CASES {
+        let args: Vec<String> = (0..rng.below(4)).map(|_| rng.string(NUMERIC, 8)).collect();
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+        if let Ok(command_parser::Selection::Tasks(selected)) =
+            command_parser::parse_selection(&args)
+        {
+            assert!(!selected.is_empty());
+
+            selected.iter().for_each(|piece| match piece {
+                command_parser::Selected::Range(from, to) => assert!(0 < *from && from <= to),
+                command_parser::Selected::One(TaskRef::Position(position)) => {
+                    assert!(*position > 0)
+                }
+                command_parser::Selected::One(TaskRef::Path(path)) => {
+                    assert!(path.len() > 1 && path.iter().all(|p| *p > 0))
+                }
+                command_parser::Selected::One(TaskRef::Id(..)) => (),
+            });
+        }
+    }
+}
+
+#[test]
+fn test_quote_round_trip() {
+    let mut rng = Rng(0x5eed_0004);
+
+    for _ in 0..CASES {
+        let words: Vec<String> = (0..rng.below(4) + 1)
+            .map(|_| rng.string(LINE, 12))
+            .filter(|w| w != "--")
+            .collect();
+        let line: Vec<String> = words.iter().map(|w| command_parser::quote(w)).collect();
+
+        let result = command_parser::parse(&format!("cmd {}", line.join(" "))).unwrap();
+        assert_eq!(result.arguments, words);
+    }
+}
+
+#[test]
+fn test_parse_never_panics() {
+    let mut rng = Rng(0x5eed_0005);
+
+    for _ in 0..CASES {
+        let line = rng.string(LINE, 40);
+
+        if let Ok(result) = command_parser::parse(&line) {
+            for n in 0..=result.arguments.len() + 1 {
+                let _ = result.rest_after(n);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_process_never_panics() {
+    let mut rng = Rng(0x5eed_0006);
+    let mut list = List::new().with_limit(Some(20));
+    let mut state = State::new();
+
+    for _ in 0..CASES {
+        let args: Vec<String> = (0..rng.below(4)).map(|_| rng.string(NUMERIC, 6)).collect();
+        let line = format!("{} {}", rng.pick(COMMANDS), args.join(" "));
+
+        let result = actions::process(line.clone(), &mut list, &mut state);
+
+        if let ActionResult::InvalidArguments(reason) = &result {
+            assert!(!reason.to_string().is_empty(), "{line:?}");
+        }
+
+        assert!(list.indices().len() <= 20, "{line:?}");
+    }
+}
@@ -1,80 +1,87 @@
 mod actions;
+mod cli;
 mod command_parser;
 mod config;
+mod date;
+#[cfg(test)]
+mod fuzz;
 mod models;
+mod query;
+mod storage;
 mod utils;
 
 use crate::actions::ActionResult;
+use crate::config::{Config, ENV_LIST};
 use crate::models::list::List;
 use crate::models::state::State;
+use std::{
+    env,
+    io::{self, IsTerminal},
+};
 
 pub fn run() {
-    let mut list = List::new();
-    let mut state = State::new();
+    let config = Config::load(&config::config_path());
+    let mut list = List::new()
+        .with_limit(config.max_tasks)
+        .with_history(config.history_depth)
+        .with_trash(config.trash_size);
+    let path = storage::data_file_path();
+    let mut state = State::new()
+        .with_data_path(&path)
+        .with_backups(config.backups)
+        .with_views(&config)
+        .with_highlight(io::stdout().is_terminal());
 
     println!("-------todocli-------");
 
-    loop {
-        print!("> ");
-
-        match actions::process(utils::get_user_input(), &mut list, &mut state) {
-            ActionResult::Sh => continue,
-
-            ActionResult::Ok => {
-                println!("ok");
-                continue;
-            }
+    let loaded = storage::load(&path, &mut list);
 
-            ActionResult::Terminate => {
-                println!("bye!");
-                break;
-            }
-
-            ActionResult::ListFull => {
-                println!("list full");
-            }
+    // Never overwrite a file that could not be read.
+    state.autosave = loaded == ActionResult::Ok;
 
-            ActionResult::ListEmpty => {
-                println!("list empty");
-            }
-
-            ActionResult::TaskNotFound => {
-                println!("task not found");
-            }
-
-            ActionResult::FileReadError => {
-                println!("file read error (cannot parse)");
-            }
+    if !state.autosave {
+        if let Some(message) = loaded.message() {
+            println!("{message}: {path}, autosave disabled");
+        }
+    }
 
-            ActionResult::UnknownCommand => {
-                println!("unknown command");
-            }
+    list.clear_history();
 
-            ActionResult::InvalidArguments => {
-                println!("invalid arguments");
-            }
+    loop {
+        print!("{}> ", state.list_name());
 
-            ActionResult::NeedConfirm => {
-                println!("confirm? (y/n)");
-            }
+        let result = actions::process(utils::get_user_input(), &mut list, &mut state);
 
-            ActionResult::NeedFilePath => {
-                println!("provide file path");
-            }
+        if let Some(message) = result.message() {
+            println!("{message}");
+        }
 
-            ActionResult::NeedTask => {
-                println!("enter task");
-            }
+        let changed = result == ActionResult::Ok || result == ActionResult::Terminate;
 
-            ActionResult::CannotSave => {
-                println!("cannot save");
-            }
+        // `use` may have switched lists, so save to the one now in use.
+        if let (true, Some(path)) = (state.autosave && changed, state.list_path()) {
+            let saved = storage::save(&path, &list, config.backups);
 
-            ActionResult::CannotLoad => {
-                println!("cannot load");
+            if saved != ActionResult::Ok {
+                if let Some(message) = saved.message() {
+                    println!("{message}: {path}");
+                }
             }
+        }
 
-            ActionResult::Feedback(feedback) => println!("{feedback}"),
+        if result == ActionResult::Terminate {
+            break;
         }
     }
 }
+
+pub fn run_args(args: &[String]) -> i32 {
+    let config = Config::load(&config::config_path());
+    let list_name = env::var(ENV_LIST).ok();
+    cli::run(
+        args,
+        &storage::data_file_path(),
+        list_name.as_deref(),
+        &config,
+    )
+}
@@ -1,3 +1,11 @@
+use std::{env, process};
+
 fn main() {
-    todocli::run();
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    if args.is_empty() {
+        todocli::run();
+    } else {
+        process::exit(todocli::run_args(&args));
+    }
 }
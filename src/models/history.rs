@@ -0,0 +1,120 @@
+use std::collections::VecDeque;
+
+// Bounded undo/redo stacks of full snapshots. Recording a new snapshot
+// drops the redo stack, like in any editor.
+pub struct History<T> {
+    undo: VecDeque<T>,
+    redo: Vec<T>,
+    depth: usize,
+}
+
+impl<T> History<T> {
+    pub fn new(depth: usize) -> Self {
+        Self {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            depth,
+        }
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    fn push_undo(&mut self, snapshot: T) {
+        self.undo.push_back(snapshot);
+
+        while self.undo.len() > self.depth {
+            self.undo.pop_front();
+        }
+    }
+
+    pub fn record(&mut self, snapshot: T) {
+        self.push_undo(snapshot);
+        self.redo.clear();
+    }
+
+    // Swaps `current` for the latest recorded snapshot.
+    pub fn undo(&mut self, current: T) -> Result<T, T> {
+        match self.undo.pop_back() {
+            Some(previous) => {
+                self.redo.push(current);
+                Ok(previous)
+            }
+            None => Err(current),
+        }
+    }
+
+    pub fn redo(&mut self, current: T) -> Result<T, T> {
+        match self.redo.pop() {
+            Some(next) => {
+                self.push_undo(current);
+                Ok(next)
+            }
+            None => Err(current),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_undo_redo() {
+        let mut history = History::new(10);
+        history.record(1);
+        history.record(2);
+
+        assert_eq!(history.undo(3), Ok(2));
+        assert_eq!(history.undo(2), Ok(1));
+        assert_eq!(history.undo(1), Err(1));
+        assert_eq!(history.redo(1), Ok(2));
+        assert_eq!(history.redo(2), Ok(3));
+        assert_eq!(history.redo(3), Err(3));
+    }
+
+    #[test]
+    fn test_record_clears_redo() {
+        let mut history = History::new(10);
+        history.record(1);
+
+        assert_eq!(history.undo(2), Ok(1));
+        history.record(1);
+        assert_eq!(history.redo(5), Err(5));
+    }
+
+    #[test]
+    fn test_depth() {
+        let mut history = History::new(2);
+        history.record(1);
+        history.record(2);
+        history.record(3);
+
+        assert_eq!(history.undo(4), Ok(3));
+        assert_eq!(history.undo(3), Ok(2));
+        assert_eq!(history.undo(2), Err(2));
+    }
+
+    #[test]
+    fn test_zero_depth() {
+        let mut history = History::new(0);
+        history.record(1);
+
+        assert_eq!(history.undo(2), Err(2));
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut history = History::new(10);
+        history.record(1);
+        history.clear();
+
+        assert_eq!(history.undo(2), Err(2));
+    }
+}
@@ -0,0 +1,222 @@
+use super::list::{Error, Task, Trashed};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+pub const SCHEMA_VERSION: u64 = 10;
+
+#[derive(Serialize)]
+struct DocumentRef<'a> {
+    version: u64,
+    tasks: &'a [Task],
+    archive: &'a [Task],
+    trash: &'a [Trashed],
+    next_id: u64,
+}
+
+#[derive(Deserialize)]
+pub struct Document {
+    pub tasks: Vec<Task>,
+    #[serde(default)]
+    pub archive: Vec<Task>,
+    #[serde(default)]
+    pub trash: Vec<Trashed>,
+    #[serde(default)]
+    pub next_id: Option<u64>,
+}
+
+// Brings a document written by an older schema up to SCHEMA_VERSION.
+// Every future schema change adds a step here keyed by the old version.
+fn migrate(mut document: Value) -> Result<Value, Error> {
+    loop {
+        match document.get("version").and_then(Value::as_u64) {
+            Some(SCHEMA_VERSION) => return Ok(document),
+            // Version 2 added `parent`, version 1 tasks are all top-level.
+            Some(1) => document["version"] = Value::from(2),
+            // Version 3 added `blocked_by`, older tasks wait for nothing.
+            Some(2) => document["version"] = Value::from(3),
+            // Version 4 added `recurrence`, older tasks do not repeat.
+            Some(3) => document["version"] = Value::from(4),
+            // Version 5 added `created_at` and `completed_at`, unknown for
+            // older tasks.
+            Some(4) => document["version"] = Value::from(5),
+            // Version 6 replaced the `is_done` flag with `status`.
+            Some(5) => {
+                let tasks = document.get_mut("tasks").and_then(Value::as_array_mut);
+                for task in tasks.into_iter().flatten().filter_map(Value::as_object_mut) {
+                    if let Some(is_done) = task.remove("is_done").and_then(|v| v.as_bool()) {
+                        let status = if is_done { "done" } else { "todo" };
+                        task.insert("status".to_string(), Value::from(status));
+                    }
+                }
+                document["version"] = Value::from(6);
+            }
+            // Version 7 added the `archive`, older files have none.
+            Some(6) => document["version"] = Value::from(7),
+            // Version 8 added the `trash`, older files have none.
+            Some(7) => document["version"] = Value::from(8),
+            // Version 9 added `next_id`, older files count on from the
+            // highest id.
+            Some(8) => document["version"] = Value::from(9),
+            // Version 10 added `waiting` to trash entries, older ones
+            // bring back no dependencies.
+            Some(9) => document["version"] = Value::from(10),
+            Some(..) => return Err(Error::UnsupportedVersion),
+            None => return Err(Error::InvalidPattern),
+        }
+    }
+}
+
+pub fn to_json(tasks: &[Task], archive: &[Task], trash: &[Trashed], next_id: u64) -> String {
+    let document = DocumentRef {
+        version: SCHEMA_VERSION,
+        tasks,
+        archive,
+        trash,
+        next_id,
+    };
+
+    serde_json::to_string_pretty(&document).expect("tasks are always serializable")
+}
+
+pub fn from_json(text: &str) -> Result<Document, Error> {
+    let document: Value = serde_json::from_str(text).map_err(|_| Error::InvalidPattern)?;
+    serde_json::from_value(migrate(document)?).map_err(|_| Error::InvalidPattern)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{list::TaskStatus, priority::Priority};
+
+    fn task(text: &str, status: TaskStatus) -> Task {
+        let mut task = Task::new(text.to_string());
+        task.status = status;
+        task
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let tasks = vec![
+            task("a; [b] c", TaskStatus::Done),
+            task("  spaced  ", TaskStatus::Waiting),
+        ];
+        let Document {
+            tasks: result,
+            archive,
+            trash,
+            next_id,
+        } = from_json(&to_json(&tasks, &[], &[], 3)).unwrap();
+
+        assert!(result.len() == 2);
+        assert!(result[0].text == "a; [b] c");
+        assert_eq!(result[0].status, TaskStatus::Done);
+        assert!(result[1].text == "  spaced  ");
+        assert_eq!(result[1].status, TaskStatus::Waiting);
+        assert!(archive.is_empty() && trash.is_empty());
+        assert_eq!(next_id, Some(3));
+    }
+
+    #[test]
+    fn test_archive_round_trip() {
+        let Document { tasks, archive, .. } = from_json(&to_json(
+            &[task("a", TaskStatus::Todo)],
+            &[task("b", TaskStatus::Done)],
+            &[],
+            1,
+        ))
+        .unwrap();
+        assert_eq!(tasks[0].text, "a");
+        assert_eq!(archive[0].text, "b");
+        assert_eq!(archive[0].status, TaskStatus::Done);
+    }
+
+    #[test]
+    fn test_to_json_version() {
+        let document: Value = serde_json::from_str(&to_json(&[], &[], &[], 1)).unwrap();
+        assert_eq!(document["version"], SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn test_from_json_defaults() {
+        let result = from_json(r#"{"version": 1, "tasks": [{"text": "a", "is_done": false}]}"#);
+        let task = &result.unwrap().tasks[0];
+        assert_eq!(task.priority, Priority::None);
+        assert!(task.due.is_none());
+        assert!(task.projects.is_empty());
+        assert!(task.contexts.is_empty());
+    }
+
+    #[test]
+    fn test_from_json_invalid() {
+        assert!(matches!(from_json("not json"), Err(Error::InvalidPattern)));
+        assert!(matches!(
+            from_json(r#"{"tasks": []}"#),
+            Err(Error::InvalidPattern)
+        ));
+        assert!(matches!(
+            from_json(r#"{"version": 1, "tasks": [{"text": 1}]}"#),
+            Err(Error::InvalidPattern)
+        ));
+    }
+
+    #[test]
+    fn test_from_json_version_1() {
+        let Document {
+            tasks,
+            archive,
+            trash,
+            next_id,
+        } = from_json(r#"{"version": 1, "tasks": [{"text": "a", "is_done": true}]}"#).unwrap();
+        let task = &tasks[0];
+        assert_eq!(task.status, TaskStatus::Done);
+        assert!(task.parent.is_none());
+        assert!(task.blocked_by.is_empty());
+        assert!(task.recurrence.is_none());
+        assert!(task.created_at.is_none());
+        assert!(task.completed_at.is_none());
+        assert!(archive.is_empty() && trash.is_empty());
+        assert!(next_id.is_none());
+    }
+
+    #[test]
+    fn test_from_json_version_5_status() {
+        let result = from_json(
+            r#"{"version": 5, "tasks": [
+                {"text": "a", "is_done": true},
+                {"text": "b", "is_done": false}
+            ]}"#,
+        )
+        .unwrap()
+        .tasks;
+        assert_eq!(result[0].status, TaskStatus::Done);
+        assert_eq!(result[1].status, TaskStatus::Todo);
+    }
+
+    #[test]
+    fn test_status_written_lowercase() {
+        let document: Value =
+            serde_json::from_str(&to_json(&[task("a", TaskStatus::Started)], &[], &[], 1)).unwrap();
+        assert_eq!(document["tasks"][0]["status"], "started");
+        assert!(document["tasks"][0].get("is_done").is_none());
+    }
+
+    #[test]
+    fn test_parent_round_trip() {
+        let mut child = task("b", TaskStatus::Todo);
+        child.parent = Some(1);
+
+        let result = from_json(&to_json(&[task("a", TaskStatus::Todo), child], &[], &[], 1))
+            .unwrap()
+            .tasks;
+        assert!(result[0].parent.is_none());
+        assert_eq!(result[1].parent, Some(1));
+    }
+
+    #[test]
+    fn test_from_json_unsupported_version() {
+        assert!(matches!(
+            from_json(r#"{"version": 99, "tasks": []}"#),
+            Err(Error::UnsupportedVersion)
+        ));
+    }
+}
@@ -1,254 +1,3029 @@
-use crate::{config::TASKS_LIST_MAX_CAPACITY, utils};
+use super::{history::History, json, priority::Priority, recurrence::Recurrence};
+use crate::{
+    config::{DEFAULT_HISTORY_DEPTH, DEFAULT_TRASH_SIZE, ID_PREFIX, PATH_SEPARATOR},
+    date::{Date, Timestamp},
+};
 use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+    str::FromStr,
+};
 
 const DONE_MARK: &str = "[+]";
 const UNDONE_MARK: &str = "[ ]";
+const STARTED_MARK: &str = "[>]";
+const WAITING_MARK: &str = "[~]";
+const CANCELLED_MARK: &str = "[-]";
+const DUE_PREFIX: &str = "due:";
+const BLOCKER_PREFIX: &str = "after:";
+const RECURRENCE_PREFIX: &str = "repeat:";
+const CREATED_PREFIX: &str = "created:";
+const COMPLETED_PREFIX: &str = "completed:";
+const REMOVED_PREFIX: &str = "removed:";
+const POSITION_PREFIX: &str = "at:";
+const UNDER_PREFIX: &str = "under:";
+const WAITING_PREFIX: &str = "waiting:";
+const WAITING_SEPARATOR: char = '>';
+const PROJECT_PREFIX: char = '+';
+const CONTEXT_PREFIX: char = '@';
+const OVERDUE_FLAG: &str = "(overdue)";
+const DUE_TODAY_FLAG: &str = "(due today)";
+const BLOCKED_FLAG: &str = "(blocked)";
+const HIGHLIGHT_START: &str = "\x1b[1;4m";
+const HIGHLIGHT_END: &str = "\x1b[0m";
+const INDENT: &str = "  ";
+const ARCHIVE_HEADER: &str = "archive:";
+const TRASH_HEADER: &str = "trash:";
+const NEXT_ID_PREFIX: &str = "next:";
 
 #[derive(Debug)]
 pub enum Error {
     CapacityExceeded,
     ItemNotFound,
     InvalidPattern,
+    UnsupportedVersion,
+    NothingToUndo,
+    NothingToRedo,
+    DependencyCycle,
 }
 
+pub enum Format {
+    Text,
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortKey {
+    Text,
+    Created,
+    Due,
+    Priority,
+}
+
+impl FromStr for SortKey {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "text" => Ok(SortKey::Text),
+            "created" => Ok(SortKey::Created),
+            "due" => Ok(SortKey::Due),
+            "priority" | "prio" => Ok(SortKey::Priority),
+            _ => Err(()),
+        }
+    }
+}
+
+// Where a task stands. Todo, started and waiting tasks are still open,
+// done and cancelled ones are closed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskStatus {
+    #[default]
+    Todo,
+    Started,
+    Waiting,
+    Done,
+    Cancelled,
+}
+
+impl TaskStatus {
+    const ALL: [TaskStatus; 5] = [
+        TaskStatus::Todo,
+        TaskStatus::Started,
+        TaskStatus::Waiting,
+        TaskStatus::Done,
+        TaskStatus::Cancelled,
+    ];
+
+    pub fn mark(&self) -> &'static str {
+        match self {
+            TaskStatus::Todo => UNDONE_MARK,
+            TaskStatus::Started => STARTED_MARK,
+            TaskStatus::Waiting => WAITING_MARK,
+            TaskStatus::Done => DONE_MARK,
+            TaskStatus::Cancelled => CANCELLED_MARK,
+        }
+    }
+
+    // Spaces inside the brackets do not matter, so `[ + ]` is done.
+    pub fn from_mark(mark: &str) -> Option<Self> {
+        let mark: String = mark.chars().filter(|c| !c.is_whitespace()).collect();
+
+        Self::ALL
+            .into_iter()
+            .find(|s| s.mark().replace(' ', "") == mark)
+    }
+
+    pub fn is_open(&self) -> bool {
+        matches!(
+            self,
+            TaskStatus::Todo | TaskStatus::Started | TaskStatus::Waiting
+        )
+    }
+}
+
+impl FromStr for TaskStatus {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "todo" => Ok(TaskStatus::Todo),
+            "started" => Ok(TaskStatus::Started),
+            "waiting" => Ok(TaskStatus::Waiting),
+            "done" => Ok(TaskStatus::Done),
+            "cancelled" | "canceled" => Ok(TaskStatus::Cancelled),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Format {
+    pub fn from_path(path: &str) -> Self {
+        match path.to_lowercase().ends_with(".json") {
+            true => Format::Json,
+            false => Format::Text,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Task {
+    #[serde(default)]
+    pub id: u64,
     pub text: String,
-    pub is_done: bool,
+    pub status: TaskStatus,
+    #[serde(default)]
+    pub priority: Priority,
+    #[serde(default)]
+    pub due: Option<Date>,
+    #[serde(default)]
+    pub projects: Vec<String>,
+    #[serde(default)]
+    pub contexts: Vec<String>,
+    // Id of the task this one is a subtask of.
+    #[serde(default)]
+    pub parent: Option<u64>,
+    // Ids of the tasks that have to be done before this one.
+    #[serde(default)]
+    pub blocked_by: Vec<u64>,
+    #[serde(default)]
+    pub recurrence: Option<Recurrence>,
+    #[serde(default)]
+    pub created_at: Option<Timestamp>,
+    #[serde(default)]
+    pub completed_at: Option<Timestamp>,
+}
+
+fn tag_name(token: &str, prefix: char) -> Option<&str> {
+    token
+        .strip_prefix(prefix)
+        .filter(|name| !name.is_empty() && !name.contains(char::is_whitespace))
+}
+
+// Pulls todo.txt style `+project` and `@context` tokens out of `text`,
+// keeping the spacing of everything else. Tags may follow any whitespace,
+// which becomes a plain space once they are taken out.
+fn extract_tags(text: &str) -> (String, Vec<String>, Vec<String>) {
+    let mut projects: Vec<String> = Vec::new();
+    let mut contexts: Vec<String> = Vec::new();
+
+    let words: Vec<&str> = text
+        .split(char::is_whitespace)
+        .filter(|word| {
+            if let Some(name) = tag_name(word, PROJECT_PREFIX) {
+                if !projects.iter().any(|p| p == name) {
+                    projects.push(name.to_string());
+                }
+                false
+            } else if let Some(name) = tag_name(word, CONTEXT_PREFIX) {
+                if !contexts.iter().any(|c| c == name) {
+                    contexts.push(name.to_string());
+                }
+                false
+            } else {
+                true
+            }
+        })
+        .collect();
+
+    match projects.is_empty() && contexts.is_empty() {
+        true => (text.to_string(), projects, contexts),
+        false => (words.join(" ").trim().to_string(), projects, contexts),
+    }
+}
+
+impl Task {
+    pub fn new(text: String) -> Self {
+        let mut task = Self {
+            id: 0,
+            text: String::new(),
+            status: TaskStatus::Todo,
+            priority: Priority::None,
+            due: None,
+            projects: Vec::new(),
+            contexts: Vec::new(),
+            parent: None,
+            blocked_by: Vec::new(),
+            recurrence: None,
+            created_at: None,
+            completed_at: None,
+        };
+
+        task.set_text(text);
+        task
+    }
+
+    pub fn set_text(&mut self, text: String) {
+        let (text, projects, contexts) = extract_tags(&text);
+
+        self.text = text;
+        self.projects = projects;
+        self.contexts = contexts;
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        match (tag_name(tag, PROJECT_PREFIX), tag_name(tag, CONTEXT_PREFIX)) {
+            (Some(name), _) => self.projects.iter().any(|p| p == name),
+            (_, Some(name)) => self.contexts.iter().any(|c| c == name),
+            _ => false,
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.status == TaskStatus::Done
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.status.is_open()
+    }
+
+    pub fn is_overdue(&self, today: Date) -> bool {
+        self.is_open() && self.due.is_some_and(|d| d < today)
+    }
+
+    pub fn is_due_today(&self, today: Date) -> bool {
+        self.is_open() && self.due == Some(today)
+    }
+
+    // `label` is the dotted position of the task, indented by its `depth`.
+    // Timestamps are only written in the `long` form.
+    fn to_line(&self, label: &str, depth: usize, text: &str, flags: &[&str], long: bool) -> String {
+        let status = self.status.mark();
+
+        let due = self.due.map(|d| format!("{DUE_PREFIX}{d}"));
+        let recurrence = self
+            .recurrence
+            .as_ref()
+            .map(|r| format!("{RECURRENCE_PREFIX}{r}"));
+        let times = [
+            self.created_at.map(|t| format!("{CREATED_PREFIX}{t}")),
+            self.completed_at.map(|t| format!("{COMPLETED_PREFIX}{t}")),
+        ];
+        let projects = self.projects.iter().map(|p| format!("{PROJECT_PREFIX}{p}"));
+        let contexts = self.contexts.iter().map(|c| format!("{CONTEXT_PREFIX}{c}"));
+        let blockers = self
+            .blocked_by
+            .iter()
+            .map(|id| format!("{BLOCKER_PREFIX}{ID_PREFIX}{id}"));
+        let tags: Vec<String> = projects.chain(contexts).chain(blockers).collect();
+
+        let parts: Vec<&str> = [Some(status), self.priority.mark(), Some(text)]
+            .into_iter()
+            .chain(tags.iter().map(|t| Some(t.as_str())))
+            .chain([recurrence.as_deref(), due.as_deref()])
+            .chain(times.iter().filter(|_| long).map(|t| t.as_deref()))
+            .flatten()
+            .chain(flags.iter().copied())
+            .collect();
+
+        format!(
+            "{}{label}) {ID_PREFIX}{} {};\n",
+            INDENT.repeat(depth),
+            self.id,
+            parts.join(" ")
+        )
+    }
+}
+
+// Splits the last word off `text` when it is `prefix` followed by a value
+// that `parse` accepts.
+fn take_token<T>(text: &mut String, prefix: &str, parse: impl Fn(&str) -> Option<T>) -> Option<T> {
+    let (rest, token) = text.rsplit_once(' ')?;
+    let value = parse(token.strip_prefix(prefix)?)?;
+
+    text.truncate(rest.len());
+    Some(value)
+}
+
+// Matches words of task text that start with the prefix of a token
+// `to_line` writes after the text, or a first word that starts with the
+// priority mark it writes before it, behind any backslashes.
+fn token_words() -> Regex {
+    let prefixes = [
+        DUE_PREFIX,
+        BLOCKER_PREFIX,
+        RECURRENCE_PREFIX,
+        CREATED_PREFIX,
+        COMPLETED_PREFIX,
+    ];
+    let prefixes: Vec<String> = prefixes.iter().map(|p| regex::escape(p)).collect();
+    let marks: Vec<String> = [Priority::Low, Priority::Medium, Priority::High]
+        .iter()
+        .filter_map(|p| p.mark())
+        .map(regex::escape)
+        .collect();
+
+    Regex::new(&format!(
+        r"(^|\s)(\\*(?:{})|\A\\*(?:{}))",
+        prefixes.join("|"),
+        marks.join("|")
+    ))
+    .unwrap()
+}
+
+// Text like `wait after:#5` would read back as a dependency and `(H) x`
+// as a priority, so files write them as `wait \after:#5` and `\(H) x`. Words already starting with backslashes
+// get one more, which `unescape_tokens` takes off again.
+fn escape_tokens(words: &Regex, text: &str) -> String {
+    words.replace_all(text, "${1}\\${2}").into_owned()
+}
+
+fn unescape_tokens(words: &Regex, text: &str) -> String {
+    words
+        .replace_all(text, |c: &regex::Captures| {
+            format!("{}{}", &c[1], c[2].strip_prefix('\\').unwrap_or(&c[2]))
+        })
+        .into_owned()
+}
+
+fn count_tags<'a>(tags: impl Iterator<Item = &'a String>) -> Vec<(String, usize)> {
+    let mut counts: Vec<(String, usize)> = Vec::new();
+
+    tags.for_each(|tag| match counts.iter_mut().find(|(t, _)| t == tag) {
+        Some((_, count)) => *count += 1,
+        None => counts.push((tag.to_string(), 1)),
+    });
+
+    counts.sort();
+    counts
+}
+
+// Moves the task at `index` and, recursively, its subtasks from `slots`
+// to the end of `result`.
+fn take_subtree(slots: &mut [Option<Task>], index: usize, result: &mut Vec<Task>) {
+    let Some(task) = slots[index].take() else {
+        return;
+    };
+
+    let id = task.id;
+    result.push(task);
+
+    let children: Vec<usize> = (0..slots.len())
+        .filter(|i| slots[*i].as_ref().is_some_and(|t| t.parent == Some(id)))
+        .collect();
+
+    children
+        .into_iter()
+        .for_each(|child| take_subtree(slots, child, result));
+}
+
+// Every subtask below the task at `index` in `tasks`, at any depth.
+fn subtree(tasks: &[Task], index: usize) -> Vec<usize> {
+    let Some(task) = tasks.get(index) else {
+        return Vec::new();
+    };
+
+    let mut ids: HashSet<u64> = HashSet::from([task.id]);
+
+    (index + 1..tasks.len())
+        .take_while(|i| match tasks[*i].parent {
+            Some(parent) if ids.contains(&parent) => ids.insert(tasks[*i].id),
+            _ => false,
+        })
+        .collect()
+}
+
+// Puts `tasks` in tree order, keeping siblings in the order given.
+// Subtasks whose parent is missing become top-level tasks, as does
+// the first task of any cycle of parents.
+fn arrange_tree(tasks: &mut Vec<Task>) {
+    let ids: HashSet<u64> = tasks.iter().map(|t| t.id).collect();
+    let mut slots: Vec<Option<Task>> = tasks.drain(..).map(Some).collect();
+
+    slots.iter_mut().flatten().for_each(|task| {
+        if task
+            .parent
+            .is_some_and(|p| p == task.id || !ids.contains(&p))
+        {
+            task.parent = None;
+        }
+    });
+
+    while let Some(first) = slots.iter().position(Option::is_some) {
+        let index = slots
+            .iter()
+            .position(|t| t.as_ref().is_some_and(|t| t.parent.is_none()))
+            .unwrap_or(first);
+
+        if let Some(task) = slots[index].as_mut() {
+            task.parent = None;
+        }
+
+        take_subtree(&mut slots, index, tasks);
+    }
+}
+
+// The dotted position (`3.2` is the second subtask of the third
+// top-level task) and the depth of every task.
+fn outline(tasks: &[Task]) -> Vec<(String, usize)> {
+    let mut result: Vec<(String, usize)> = Vec::with_capacity(tasks.len());
+    let mut positions: HashMap<u64, usize> = HashMap::new();
+    let mut children: Vec<usize> = vec![0; tasks.len()];
+    let mut roots = 0;
+
+    tasks.iter().enumerate().for_each(|(i, task)| {
+        let entry = match task.parent.and_then(|id| positions.get(&id)) {
+            Some(parent) => {
+                children[*parent] += 1;
+                let (label, depth) = &result[*parent];
+                (
+                    format!("{label}{PATH_SEPARATOR}{}", children[*parent]),
+                    depth + 1,
+                )
+            }
+            None => {
+                roots += 1;
+                (roots.to_string(), 0)
+            }
+        };
+
+        positions.insert(task.id, i);
+        result.push(entry);
+    });
+
+    result
+}
+
+// The tasks `to_text` wrote to `text`, each with the position of its
+// parent. Blank text holds no tasks.
+fn parse_tasks(text: &str) -> Result<(Vec<Task>, Vec<Option<usize>>), Error> {
+    if text.trim().is_empty() {
+        return Ok((Vec::new(), Vec::new()));
+    }
+
+    let mut result: Vec<Task> = Vec::new();
+    let mut labels: Vec<Option<&str>> = Vec::new();
+    let words = self::token_words();
+    let re =
+        Regex::new(r"(?:(\d+(?:\.\d+)*)\)\s*)?(?:#(\d+)\s+)?(\[\s*[+>~-]?\s*\])(([^;\[\]])+)(;)")
+            .unwrap();
+
+    if !re.is_match(text) {
+        return Err(Error::InvalidPattern);
+    }
+
+    re.captures_iter(text).for_each(|c| {
+        labels.push(c.get(1).map(|label| label.as_str()));
+
+        let id = c.get(2).and_then(|id| id.as_str().parse::<u64>().ok());
+        let check = c.get(3).unwrap().as_str();
+        let text = c.get(4).unwrap().as_str().trim().to_string();
+
+        let (priority, text) = match text.split_once(' ') {
+            Some((mark, rest)) => match Priority::from_mark(mark) {
+                Some(priority) => (priority, rest.to_string()),
+                None => (Priority::None, text),
+            },
+            None => (Priority::None, text),
+        };
+
+        // Trailing tokens come off in the reverse of the order
+        // `to_line` writes them in.
+        let mut text = text;
+        let completed_at = take_token(&mut text, COMPLETED_PREFIX, |t| t.parse().ok());
+        let created_at = take_token(&mut text, CREATED_PREFIX, |t| t.parse().ok());
+        let due = take_token(&mut text, DUE_PREFIX, |d| d.parse::<Date>().ok());
+        let recurrence = take_token(&mut text, RECURRENCE_PREFIX, |r| r.parse().ok());
+        let mut blocked_by: Vec<u64> = Vec::new();
+
+        while let Some(id) = take_token(&mut text, BLOCKER_PREFIX, |b| {
+            b.strip_prefix(ID_PREFIX)?.parse().ok()
+        }) {
+            blocked_by.insert(0, id);
+        }
+
+        let mut task = Task::new(self::unescape_tokens(&words, &text));
+        task.id = id.unwrap_or(0);
+        task.status = TaskStatus::from_mark(check).unwrap_or_default();
+        task.priority = priority;
+        task.due = due;
+        task.blocked_by = blocked_by;
+        task.recurrence = recurrence;
+        task.created_at = created_at;
+        task.completed_at = completed_at;
+
+        result.push(task);
+    });
+
+    // A task labelled `3.2` is a subtask of the closest task above
+    // it labelled `3`.
+    let parents: Vec<Option<usize>> = labels
+        .iter()
+        .enumerate()
+        .map(|(i, label)| {
+            let (parent, _) = (*label)?.rsplit_once(PATH_SEPARATOR)?;
+            labels[..i].iter().rposition(|l| *l == Some(parent))
+        })
+        .collect();
+
+    Ok((result, parents))
+}
+
+// Splits tasks taken out together into subtrees, each headed by a task
+// whose parent was not taken and keeping the position of that task.
+fn split_subtrees(taken: Vec<(usize, Task)>) -> Vec<(usize, Vec<Task>)> {
+    let ids: HashSet<u64> = taken.iter().map(|(_, t)| t.id).collect();
+    let mut result: Vec<(usize, Vec<Task>)> = Vec::new();
+
+    taken
+        .into_iter()
+        .for_each(|(position, task)| match result.last_mut() {
+            Some((_, tasks)) if task.parent.is_some_and(|p| ids.contains(&p)) => tasks.push(task),
+            _ => result.push((position, vec![task])),
+        });
+
+    result
+}
+
+// A task removed along with its subtasks. The first task keeps the id of
+// its old parent so it can go back under it.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Trashed {
+    removed_at: Timestamp,
+    position: usize,
+    tasks: Vec<Task>,
+    // Tasks left in the list that waited for tasks of the entry, as
+    // `(task id, blocker id)`, so they wait again once it is restored.
+    #[serde(default)]
+    waiting: Vec<(u64, u64)>,
+}
+
+// The id `to_text` noted on a first `next:#12` line, and the rest of
+// `text`.
+fn split_next_id(text: &str) -> Result<(Option<u64>, &str), Error> {
+    let Some(rest) = text.trim_start().strip_prefix(NEXT_ID_PREFIX) else {
+        return Ok((None, text));
+    };
+
+    let (line, rest) = rest.split_once('\n').unwrap_or((rest, ""));
+    let id = line
+        .trim()
+        .strip_prefix(ID_PREFIX)
+        .and_then(|id| id.parse().ok())
+        .ok_or(Error::InvalidPattern)?;
+
+    Ok((Some(id), rest))
+}
+
+// Splits `text` at a line holding only `header`.
+fn split_section<'a>(text: &'a str, header: &str) -> (&'a str, &'a str) {
+    let header = Regex::new(&format!(r"(?m)^\s*{header}\s*$")).unwrap();
+
+    match header.find(text) {
+        Some(m) => (&text[..m.start()], &text[m.end()..]),
+        None => (text, ""),
+    }
+}
+
+// The trash `to_text` wrote to `text`. The first task of every entry ends
+// with when it was removed, where it stood, for subtasks the id of its
+// parent and the tasks that waited for it (`removed:2026-10-18T14:03:05Z
+// at:2 under:#3 waiting:#7>#4`, where #7 waited for #4).
+fn parse_trash(text: &str) -> Result<(Vec<Trashed>, Vec<Option<usize>>), Error> {
+    let waiting = format!(r"{WAITING_PREFIX}{ID_PREFIX}(\d+){WAITING_SEPARATOR}{ID_PREFIX}(\d+)");
+    let marks = Regex::new(&format!(
+        r" {REMOVED_PREFIX}(\S+) {POSITION_PREFIX}(\d+)(?: {UNDER_PREFIX}{ID_PREFIX}(\d+))?((?: {waiting})*);"
+    ))
+    .unwrap();
+    let waiting = Regex::new(&waiting).unwrap();
+
+    let mut entries: Vec<Trashed> = Vec::new();
+    let mut heads: Vec<Option<u64>> = Vec::new();
+
+    for c in marks.captures_iter(text) {
+        let removed_at = c[1].parse().map_err(|_| Error::InvalidPattern)?;
+        let position = c[2].parse().map_err(|_| Error::InvalidPattern)?;
+        let parent = match c.get(3) {
+            Some(id) => Some(id.as_str().parse().map_err(|_| Error::InvalidPattern)?),
+            None => None,
+        };
+
+        let waiting = waiting
+            .captures_iter(&c[4])
+            .map(|w| Ok((w[1].parse()?, w[2].parse()?)))
+            .collect::<Result<Vec<(u64, u64)>, std::num::ParseIntError>>()
+            .map_err(|_| Error::InvalidPattern)?;
+
+        entries.push(Trashed {
+            removed_at,
+            position,
+            tasks: Vec::new(),
+            waiting,
+        });
+        heads.push(parent);
+    }
+
+    let (tasks, parents) = self::parse_tasks(&marks.replace_all(text, ";"))?;
+
+    if parents.iter().filter(|p| p.is_none()).count() != entries.len() {
+        return Err(Error::InvalidPattern);
+    }
+
+    let mut entry = 0;
+
+    tasks
+        .into_iter()
+        .zip(&parents)
+        .for_each(|(mut task, parent)| {
+            if parent.is_none() {
+                task.parent = heads[entry];
+                entry += 1;
+            }
+
+            entries[entry - 1].tasks.push(task);
+        });
+
+    Ok((entries, parents))
+}
+
+struct Snapshot {
+    tasks: Vec<Task>,
+    archive: Vec<Task>,
+    trash: Vec<Trashed>,
+    next_id: u64,
+}
+
+// Tasks are kept in tree order: every subtask comes right after its
+// parent and the parent's earlier subtasks, along with their own.
+pub struct List {
+    tasks: Vec<Task>,
+    // Tasks put away by `archive`, in tree order like `tasks`.
+    archive: Vec<Task>,
+    // What `remove` and `clear` took out, newest first.
+    trash: Vec<Trashed>,
+    trash_size: usize,
+    limit: Option<usize>,
+    next_id: u64,
+    history: History<Snapshot>,
 }
 
-pub struct List {
-    tasks: Vec<Task>,
-}
+impl List {
+    pub fn new() -> Self {
+        Self {
+            tasks: Vec::new(),
+            archive: Vec::new(),
+            trash: Vec::new(),
+            trash_size: DEFAULT_TRASH_SIZE,
+            limit: None,
+            next_id: 1,
+            history: History::new(DEFAULT_HISTORY_DEPTH),
+        }
+    }
+
+    pub fn with_limit(mut self, limit: Option<usize>) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    pub fn with_history(mut self, depth: usize) -> Self {
+        self.history = History::new(depth);
+        self
+    }
+
+    pub fn with_trash(mut self, size: usize) -> Self {
+        self.trash_size = size;
+        self
+    }
+
+    // An empty list with the same limits, to load another list into.
+    pub fn blank(&self) -> List {
+        List::new()
+            .with_limit(self.limit)
+            .with_history(self.history.depth())
+            .with_trash(self.trash_size)
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            tasks: self.tasks.clone(),
+            archive: self.archive.clone(),
+            trash: self.trash.clone(),
+            next_id: self.next_id,
+        }
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) {
+        self.tasks = snapshot.tasks;
+        self.archive = snapshot.archive;
+        self.trash = snapshot.trash;
+        self.next_id = snapshot.next_id;
+    }
+
+    // Must be called right before every change to the tasks.
+    fn record(&mut self) {
+        let snapshot = self.snapshot();
+        self.history.record(snapshot);
+    }
+
+    fn update(&mut self, index: usize, change: impl FnOnce(&mut Task)) -> Result<(), Error> {
+        if index >= self.tasks.len() {
+            return Err(Error::ItemNotFound);
+        }
+
+        self.record();
+        change(&mut self.tasks[index]);
+        Ok(())
+    }
+
+    // Applies `change` to every task in `indices` as a single undo step.
+    fn update_many(&mut self, indices: &[usize], change: impl Fn(&mut Task)) -> Result<(), Error> {
+        if indices.iter().any(|i| *i >= self.tasks.len()) {
+            return Err(Error::ItemNotFound);
+        }
+
+        self.record();
+        indices.iter().for_each(|i| change(&mut self.tasks[*i]));
+        Ok(())
+    }
+
+    pub fn undo(&mut self) -> Result<(), Error> {
+        let current = self.snapshot();
+
+        match self.history.undo(current) {
+            Ok(previous) => {
+                self.restore(previous);
+                Ok(())
+            }
+            Err(..) => Err(Error::NothingToUndo),
+        }
+    }
+
+    pub fn redo(&mut self) -> Result<(), Error> {
+        let current = self.snapshot();
+
+        match self.history.redo(current) {
+            Ok(next) => {
+                self.restore(next);
+                Ok(())
+            }
+            Err(..) => Err(Error::NothingToRedo),
+        }
+    }
+
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+    }
+
+    fn check_limit(&self, len: usize) -> Result<(), Error> {
+        match self.limit {
+            Some(limit) if len > limit => Err(Error::CapacityExceeded),
+            _ => Ok(()),
+        }
+    }
+
+    // Gives every task that has no id yet, or one already taken by an
+    // earlier task, a fresh id above all existing ones. Archived and
+    // trashed tasks count too, so they keep their ids when brought back.
+    fn assign_ids(&mut self) {
+        let mut seen: HashSet<u64> = HashSet::new();
+        self.next_id = self.next_id.max(self.max_id() + 1);
+
+        let trashed = self.trash.iter_mut().flat_map(|e| &mut e.tasks);
+        self.tasks
+            .iter_mut()
+            .chain(&mut self.archive)
+            .chain(trashed)
+            .for_each(|task| {
+                if task.id == 0 || !seen.insert(task.id) {
+                    task.id = self.next_id;
+                    self.next_id += 1;
+                }
+            });
+    }
+
+    fn max_id(&self) -> u64 {
+        let trashed = self.trash.iter().flat_map(|e| &e.tasks);
+
+        self.tasks
+            .iter()
+            .chain(&self.archive)
+            .chain(trashed)
+            .map(|t| t.id)
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn arrange(&mut self) {
+        self::arrange_tree(&mut self.tasks);
+        self::arrange_tree(&mut self.archive);
+    }
+
+    pub fn position_of(&self, id: u64) -> Option<usize> {
+        self.tasks.iter().position(|t| t.id == id)
+    }
+
+    // Finds a task by its 1-based dotted position, `[3, 2]` for `3.2`.
+    pub fn position_at(&self, path: &[usize]) -> Option<usize> {
+        let mut parent: Option<u64> = None;
+        let mut index: Option<usize> = None;
+
+        for position in path {
+            let found = (0..self.tasks.len())
+                .filter(|i| self.tasks[*i].parent == parent)
+                .nth(position.checked_sub(1)?)?;
+
+            parent = Some(self.tasks[found].id);
+            index = Some(found);
+        }
+
+        index
+    }
+
+    // Every subtask below the task at `index`, at any depth, in tree order.
+    pub fn subtasks(&self, index: usize) -> Vec<usize> {
+        self::subtree(&self.tasks, index)
+    }
+
+    pub fn get(&mut self, index: usize) -> Result<&Task, Error> {
+        match self.tasks.get(index) {
+            Some(task) => Ok(task),
+            None => Err(Error::ItemNotFound),
+        }
+    }
+
+    pub fn add(&mut self, text: String) -> Result<(), Error> {
+        self.check_limit(self.tasks.len() + 1)?;
+
+        let mut task = Task::new(text);
+        task.id = self.next_id;
+        task.created_at = Some(Timestamp::now());
+
+        self.record();
+        self.next_id += 1;
+        self.tasks.push(task);
+
+        Ok(())
+    }
+
+    // Adds a task as the last subtask of the task at `parent`.
+    pub fn add_subtask(&mut self, parent: usize, text: String) -> Result<(), Error> {
+        if parent >= self.tasks.len() {
+            return Err(Error::ItemNotFound);
+        }
+
+        self.check_limit(self.tasks.len() + 1)?;
+
+        let mut task = Task::new(text);
+        task.id = self.next_id;
+        task.parent = Some(self.tasks[parent].id);
+        task.created_at = Some(Timestamp::now());
+
+        let index = parent + self.subtasks(parent).len() + 1;
+
+        self.record();
+        self.next_id += 1;
+        self.tasks.insert(index, task);
+
+        Ok(())
+    }
+
+    // Takes the tasks at `indices` and their subtasks out of the list, in
+    // tree order and with the positions they had. Tasks left behind stop
+    // waiting for the ones taken.
+    fn take(&mut self, indices: &[usize]) -> Vec<(usize, Task)> {
+        let indices: HashSet<usize> = indices
+            .iter()
+            .flat_map(|i| std::iter::once(*i).chain(self.subtasks(*i)))
+            .collect();
+
+        let taken: HashSet<u64> = indices.iter().map(|i| self.tasks[*i].id).collect();
+        let mut result: Vec<(usize, Task)> = Vec::new();
+
+        std::mem::take(&mut self.tasks)
+            .into_iter()
+            .enumerate()
+            .for_each(|(i, task)| match indices.contains(&i) {
+                true => result.push((i, task)),
+                false => self.tasks.push(task),
+            });
+
+        self.tasks
+            .iter_mut()
+            .for_each(|t| t.blocked_by.retain(|id| !taken.contains(id)));
+
+        result
+    }
+
+    // Moves the tasks at `indices` and their subtasks to the trash, one
+    // entry per subtree, dropping the oldest entries once it is full.
+    fn discard(&mut self, indices: &[usize]) {
+        let removed_at = Timestamp::now();
+        let taken: HashSet<u64> = indices
+            .iter()
+            .flat_map(|i| std::iter::once(*i).chain(self.subtasks(*i)))
+            .map(|i| self.tasks[i].id)
+            .collect();
+        let waiting: Vec<(u64, u64)> = self
+            .tasks
+            .iter()
+            .filter(|t| !taken.contains(&t.id))
+            .flat_map(|t| t.blocked_by.iter().map(|b| (t.id, *b)))
+            .filter(|(_, b)| taken.contains(b))
+            .collect();
+
+        let entries =
+            self::split_subtrees(self.take(indices))
+                .into_iter()
+                .map(|(position, tasks)| Trashed {
+                    removed_at,
+                    position,
+                    waiting: waiting
+                        .iter()
+                        .filter(|(_, b)| tasks.iter().any(|t| t.id == *b))
+                        .copied()
+                        .collect(),
+                    tasks,
+                });
+
+        self.trash.splice(0..0, entries);
+        self.trash.truncate(self.trash_size);
+    }
+
+    // Indices refer to the list before removal, so they may come in any order.
+    // Subtasks are removed along with their parent, and all go to the trash.
+    pub fn remove(&mut self, indices: &[usize]) -> Result<(), Error> {
+        if indices.iter().any(|i| *i >= self.tasks.len()) {
+            return Err(Error::ItemNotFound);
+        }
+
+        self.record();
+        self.discard(indices);
+
+        Ok(())
+    }
+
+    // Moves the tasks at `indices`, with their subtasks, to the end of the
+    // archive.
+    pub fn archive(&mut self, indices: &[usize]) -> Result<(), Error> {
+        if indices.iter().any(|i| *i >= self.tasks.len()) {
+            return Err(Error::ItemNotFound);
+        }
+
+        self.record();
+        let taken = self::split_subtrees(self.take(indices));
+
+        taken.into_iter().for_each(|(_, mut tasks)| {
+            tasks[0].parent = None;
+            self.archive.extend(tasks);
+        });
+
+        Ok(())
+    }
+
+    // Moves the archived task at `index`, with its archived subtasks, back
+    // to the end of the list as a top-level task.
+    pub fn unarchive(&mut self, index: usize) -> Result<(), Error> {
+        if index >= self.archive.len() {
+            return Err(Error::ItemNotFound);
+        }
+
+        let count = self::subtree(&self.archive, index).len() + 1;
+        self.check_limit(self.tasks.len() + count)?;
+
+        self.record();
+        let mut restored: Vec<Task> = self.archive.drain(index..index + count).collect();
+        restored[0].parent = None;
+        self.tasks.extend(restored);
+        self.prune_blockers();
+
+        Ok(())
+    }
+
+    // A copy of the archive to view and search, addressed like the list.
+    pub fn archived(&self) -> List {
+        let mut archived = List::new();
+        archived.tasks = self.archive.clone();
+        archived.next_id = self.next_id;
+        archived
+    }
+
+    // Copies of the task at `index` and its subtasks, in tree order, to
+    // hand over to another list.
+    pub fn copy_subtree(&self, index: usize) -> Result<Vec<Task>, Error> {
+        if index >= self.tasks.len() {
+            return Err(Error::ItemNotFound);
+        }
+
+        Ok(std::iter::once(index)
+            .chain(self.subtasks(index))
+            .map(|i| self.tasks[i].clone())
+            .collect())
+    }
+
+    // Appends a subtree copied from another list as a top-level task. The
+    // tasks get fresh ids and only keep blockers that came along.
+    pub fn adopt(&mut self, mut tasks: Vec<Task>) -> Result<(), Error> {
+        self.check_limit(self.tasks.len() + tasks.len())?;
+
+        self.record();
+        let ids: HashMap<u64, u64> = tasks
+            .iter()
+            .zip(self.next_id..)
+            .map(|(t, id)| (t.id, id))
+            .collect();
+
+        tasks.iter_mut().for_each(|task| {
+            task.id = ids[&task.id];
+            task.parent = task.parent.and_then(|p| ids.get(&p).copied());
+            task.blocked_by = task
+                .blocked_by
+                .iter()
+                .filter_map(|b| ids.get(b).copied())
+                .collect();
+        });
+
+        self.next_id += tasks.len() as u64;
+        self.tasks.extend(tasks);
+
+        Ok(())
+    }
+
+    // Drops the task at `index` and its subtasks without keeping them in
+    // the trash, once another list has adopted them. History is cleared
+    // too, undo would otherwise bring them back while the other list
+    // keeps its copy.
+    pub fn release(&mut self, index: usize) -> Result<(), Error> {
+        if index >= self.tasks.len() {
+            return Err(Error::ItemNotFound);
+        }
+
+        self.take(&[index]);
+        self.clear_history();
+
+        Ok(())
+    }
+
+    pub fn alter(&mut self, index: usize, next_text: String) -> Result<(), Error> {
+        self.update(index, |task| task.set_text(next_text))
+    }
+
+    pub fn clear(&mut self) {
+        let indices = self.indices();
+
+        self.record();
+        self.discard(&indices);
+    }
+
+    // Puts the trash entry at `index` back where its tasks stood, under
+    // their old parent if it is still there.
+    pub fn restore_from_trash(&mut self, index: usize) -> Result<(), Error> {
+        let Some(entry) = self.trash.get(index) else {
+            return Err(Error::ItemNotFound);
+        };
+
+        self.check_limit(self.tasks.len() + entry.tasks.len())?;
+
+        self.record();
+        let entry = self.trash.remove(index);
+        let position = entry.position.min(self.tasks.len());
+        self.tasks.splice(position..position, entry.tasks);
+        self.arrange();
+
+        // Pruning drops these again if they would now close a cycle.
+        entry.waiting.into_iter().for_each(|(id, blocker)| {
+            if let Some(task) = self.tasks.iter_mut().find(|t| t.id == id) {
+                task.blocked_by.push(blocker);
+            }
+        });
+        self.prune_blockers();
+
+        Ok(())
+    }
+
+    pub fn empty_trash(&mut self) {
+        self.record();
+        self.trash.clear();
+    }
+
+    // A recurring task hands its rule on to a new occurrence, added right
+    // after it (and its subtasks) with the next due date.
+    pub fn mark_done(&mut self, indices: &[usize]) -> Result<(), Error> {
+        if indices.iter().any(|i| *i >= self.tasks.len()) {
+            return Err(Error::ItemNotFound);
+        }
+
+        let now = Timestamp::now();
+        let today = now.date();
+        let mut repeats: Vec<usize> = indices
+            .iter()
+            .copied()
+            .filter(|i| self.tasks[*i].is_open() && self.tasks[*i].recurrence.is_some())
+            .collect();
+        repeats.sort();
+        repeats.dedup();
+
+        self.check_limit(self.tasks.len() + repeats.len())?;
+        self.record();
+
+        let occurrences: Vec<(usize, Task)> = repeats
+            .into_iter()
+            .map(|i| {
+                let task = &mut self.tasks[i];
+                let recurrence = task.recurrence.take().expect("only recurring tasks repeat");
+
+                let mut next = task.clone();
+                next.id = self.next_id;
+                // Past the last date that can be written, it stays due
+                // when it was.
+                next.due = recurrence.next(task.due, today).or(task.due);
+                next.blocked_by.clear();
+                next.recurrence = Some(recurrence);
+                next.created_at = Some(now);
+                next.completed_at = None;
+                self.next_id += 1;
+
+                (i + self.subtasks(i).len() + 1, next)
+            })
+            .collect();
+
+        indices.iter().for_each(|i| {
+            let task = &mut self.tasks[*i];
+
+            if !task.is_done() {
+                task.status = TaskStatus::Done;
+                task.completed_at = Some(now);
+            }
+        });
+
+        occurrences
+            .into_iter()
+            .rev()
+            .for_each(|(index, task)| self.tasks.insert(index, task));
+
+        // A subtask repeating with its parent lands after the parent's
+        // next occurrence, out of its parent's subtree.
+        self.arrange();
+
+        Ok(())
+    }
+
+    // For any status but done, which `mark_done` sets.
+    pub fn set_status(&mut self, indices: &[usize], status: TaskStatus) -> Result<(), Error> {
+        self.update_many(indices, |task| {
+            task.status = status;
+            task.completed_at = None;
+        })
+    }
+
+    pub fn mark_undone(&mut self, indices: &[usize]) -> Result<(), Error> {
+        self.set_status(indices, TaskStatus::Todo)
+    }
+
+    // Makes the task at `index` wait for the task at `on`, unless `on`
+    // already waits for it, directly or through other tasks.
+    pub fn block(&mut self, index: usize, on: usize) -> Result<(), Error> {
+        if index >= self.tasks.len() || on >= self.tasks.len() {
+            return Err(Error::ItemNotFound);
+        }
+
+        let id = self.tasks[on].id;
+
+        if index == on || self.depends_on(on, self.tasks[index].id) {
+            return Err(Error::DependencyCycle);
+        }
+
+        self.update(index, |task| {
+            if !task.blocked_by.contains(&id) {
+                task.blocked_by.push(id);
+            }
+        })
+    }
+
+    // Stops the task at `index` waiting for the task at `on`, or for
+    // any task without one.
+    pub fn unblock(&mut self, index: usize, on: Option<usize>) -> Result<(), Error> {
+        let id = match on {
+            Some(on) => Some(self.tasks.get(on).ok_or(Error::ItemNotFound)?.id),
+            None => None,
+        };
+
+        self.update(index, |task| match id {
+            Some(id) => task.blocked_by.retain(|b| *b != id),
+            None => task.blocked_by.clear(),
+        })
+    }
+
+    // Whether the task at `index` waits for the task `id`, at any depth.
+    fn depends_on(&self, index: usize, id: u64) -> bool {
+        let mut seen: HashSet<u64> = HashSet::new();
+        let mut pending: Vec<u64> = self.tasks[index].blocked_by.clone();
+
+        while let Some(next) = pending.pop() {
+            if next == id {
+                return true;
+            }
+
+            if seen.insert(next) {
+                if let Some(task) = self.tasks.iter().find(|t| t.id == next) {
+                    pending.extend(&task.blocked_by);
+                }
+            }
+        }
+
+        false
+    }
+
+    // Drops dependencies on tasks that do not exist and any that would
+    // close a cycle, keeping the ones that come first.
+    fn prune_blockers(&mut self) {
+        let all: Vec<Vec<u64>> = self
+            .tasks
+            .iter_mut()
+            .map(|t| std::mem::take(&mut t.blocked_by))
+            .collect();
+
+        all.into_iter().enumerate().for_each(|(index, blockers)| {
+            blockers.into_iter().for_each(|id| {
+                if let Some(on) = self.position_of(id).filter(|on| *on != index) {
+                    if !self.depends_on(on, self.tasks[index].id)
+                        && !self.tasks[index].blocked_by.contains(&id)
+                    {
+                        self.tasks[index].blocked_by.push(id);
+                    }
+                }
+            });
+        });
+    }
+
+    // The open tasks that the task at `index` still waits for.
+    pub fn blockers(&self, index: usize) -> Vec<usize> {
+        let Some(task) = self.tasks.get(index) else {
+            return Vec::new();
+        };
+
+        task.blocked_by
+            .iter()
+            .filter_map(|id| self.position_of(*id))
+            .filter(|i| self.tasks[*i].is_open())
+            .collect()
+    }
+
+    // Open and still waiting for open tasks.
+    pub fn is_blocked(&self, task: &Task) -> bool {
+        task.is_open()
+            && task
+                .blocked_by
+                .iter()
+                .filter_map(|id| self.position_of(*id))
+                .any(|i| self.tasks[i].is_open())
+    }
+
+    pub fn set_recurrence(
+        &mut self,
+        index: usize,
+        recurrence: Option<Recurrence>,
+    ) -> Result<(), Error> {
+        self.update(index, |task| task.recurrence = recurrence)
+    }
+
+    pub fn set_priority(&mut self, index: usize, priority: Priority) -> Result<(), Error> {
+        self.update(index, |task| task.priority = priority)
+    }
+
+    pub fn set_due(&mut self, index: usize, due: Option<Date>) -> Result<(), Error> {
+        self.update(index, |task| task.due = due)
+    }
+
+    pub fn is_empty(&mut self) -> bool {
+        self.tasks.is_empty()
+    }
+
+    // Archived tasks, and then the trash, follow the list under a line of
+    // their own.
+    pub fn to_text(&self) -> String {
+        let words = self::token_words();
+        let lines = |tasks: &[Task]| -> String {
+            tasks
+                .iter()
+                .zip(self::outline(tasks))
+                .map(|(t, (label, depth))| {
+                    t.to_line(
+                        &label,
+                        depth,
+                        &self::escape_tokens(&words, &t.text),
+                        &[],
+                        true,
+                    )
+                })
+                .collect()
+        };
+
+        let mut result = lines(&self.tasks);
+
+        // Ids of tasks that are gone for good are not handed out again.
+        if self.next_id > self.max_id() + 1 {
+            result.insert_str(0, &format!("{NEXT_ID_PREFIX}{ID_PREFIX}{}\n", self.next_id));
+        }
+
+        if !self.archive.is_empty() {
+            result.push_str(&format!("{ARCHIVE_HEADER}\n{}", lines(&self.archive)));
+        }
+
+        if !self.trash.is_empty() {
+            result.push_str(&format!("{TRASH_HEADER}\n{}", self.trash_lines(true)));
+        }
+
+        result
+    }
+
+    // The trash numbered from the newest entry, each marked with when it
+    // was removed.
+    pub fn to_trash_view(&self) -> String {
+        self.trash_lines(false)
+    }
+
+    // Files also note where each entry stood, which `parse_trash` reads back.
+    fn trash_lines(&self, long: bool) -> String {
+        let mut result = String::new();
+        let words = self::token_words();
+
+        self.trash.iter().enumerate().for_each(|(n, entry)| {
+            let mut marks = vec![format!("{REMOVED_PREFIX}{}", entry.removed_at)];
+
+            if long {
+                marks.push(format!("{POSITION_PREFIX}{}", entry.position));
+
+                if let Some(parent) = entry.tasks.first().and_then(|t| t.parent) {
+                    marks.push(format!("{UNDER_PREFIX}{ID_PREFIX}{parent}"));
+                }
+
+                entry.waiting.iter().for_each(|(id, blocker)| {
+                    marks.push(format!(
+                        "{WAITING_PREFIX}{ID_PREFIX}{id}{WAITING_SEPARATOR}{ID_PREFIX}{blocker}"
+                    ));
+                });
+            }
+
+            let marks: Vec<&str> = marks.iter().map(String::as_str).collect();
+
+            entry
+                .tasks
+                .iter()
+                .zip(self::outline(&entry.tasks))
+                .for_each(|(t, (label, depth))| {
+                    let label = match label.split_once(PATH_SEPARATOR) {
+                        Some((_, rest)) => format!("{}{PATH_SEPARATOR}{rest}", n + 1),
+                        None => (n + 1).to_string(),
+                    };
+                    let flags = if depth == 0 { marks.as_slice() } else { &[] };
+                    let text = match long {
+                        true => self::escape_tokens(&words, &t.text),
+                        false => t.text.clone(),
+                    };
+
+                    result.push_str(&t.to_line(&label, depth, &text, flags, long));
+                });
+        });
+
+        result
+    }
+
+    // Renders the tasks at `indices` in the given order for display,
+    // flagging overdue and due today ones. Tasks keep their positions
+    // as indices so they can still be addressed by commands. Subtasks are
+    // indented under their parent.
+    pub fn to_view(&self, indices: &[usize], today: Date) -> String {
+        self.render(indices, today, false, |t| t.text.clone())
+    }
+
+    // Like `to_view`, with when each task was created and completed.
+    pub fn to_long_view(&self, indices: &[usize], today: Date) -> String {
+        self.render(indices, today, true, |t| t.text.clone())
+    }
+
+    // Like `to_view`, or `to_long_view` when `long`, with every match of
+    // `pattern` in the text highlighted. Empty matches are left alone,
+    // there is nothing to see in them.
+    pub fn to_highlighted_view(
+        &self,
+        indices: &[usize],
+        today: Date,
+        long: bool,
+        pattern: &Regex,
+    ) -> String {
+        self.render(indices, today, long, |t| {
+            pattern
+                .replace_all(&t.text, |c: &regex::Captures| match &c[0] {
+                    "" => String::new(),
+                    found => format!("{HIGHLIGHT_START}{found}{HIGHLIGHT_END}"),
+                })
+                .into_owned()
+        })
+    }
+
+    fn render(
+        &self,
+        indices: &[usize],
+        today: Date,
+        long: bool,
+        text: impl Fn(&Task) -> String,
+    ) -> String {
+        let mut result = String::new();
+        let outline = self::outline(&self.tasks);
+
+        indices
+            .iter()
+            .filter_map(|i| self.tasks.get(*i).map(|t| (*i, t)))
+            .for_each(|(i, t)| {
+                let mut flags: Vec<&str> = Vec::new();
+
+                if self.is_blocked(t) {
+                    flags.push(BLOCKED_FLAG);
+                }
+
+                if t.is_overdue(today) {
+                    flags.push(OVERDUE_FLAG);
+                } else if t.is_due_today(today) {
+                    flags.push(DUE_TODAY_FLAG);
+                }
+
+                let (label, depth) = &outline[i];
+                result.push_str(&t.to_line(label, *depth, &text(t), &flags, long));
+            });
+
+        result
+    }
+
+    pub fn indices(&self) -> Vec<usize> {
+        (0..self.tasks.len()).collect()
+    }
+
+    // Tasks that can be worked on now: todo or started, nothing they wait
+    // for is still open and none of their subtasks are.
+    pub fn indices_actionable(&self) -> Vec<usize> {
+        self.indices()
+            .into_iter()
+            .filter(|i| {
+                matches!(
+                    self.tasks[*i].status,
+                    TaskStatus::Todo | TaskStatus::Started
+                )
+            })
+            .filter(|i| self.blockers(*i).is_empty())
+            .filter(|i| self.subtasks(*i).iter().all(|s| !self.tasks[*s].is_open()))
+            .collect()
+    }
+
+    // Top-level done tasks without open subtasks, so only whole subtrees
+    // are archived. When given, every task done in the subtree must have
+    // been completed before `before`. Tasks completed before completion
+    // times were kept count as old enough.
+    pub fn indices_archivable(&self, before: Option<Date>) -> Vec<usize> {
+        let old_enough =
+            |t: &Task| before.is_none_or(|b| t.completed_at.is_none_or(|c| c.date() < b));
+
+        self.indices_filtered(|t| t.parent.is_none() && t.is_done() && old_enough(t))
+            .into_iter()
+            .filter(|i| {
+                self.subtasks(*i).iter().all(|s| {
+                    let subtask = &self.tasks[*s];
+                    !subtask.is_open() && (!subtask.is_done() || old_enough(subtask))
+                })
+            })
+            .collect()
+    }
+
+    // Done tasks, or all open ones.
+    pub fn indices_done(&self, is_done: bool) -> Vec<usize> {
+        match is_done {
+            true => self.indices_filtered(|t| t.is_done()),
+            false => self.indices_filtered(|t| t.is_open()),
+        }
+    }
+
+    // Orders `indices` by `key`, ties keep their current order. Tasks from
+    // before creation times were kept go first in the order of their ids,
+    // tasks without a due date go last.
+    pub fn sort_indices(&self, indices: &mut [usize], key: SortKey) {
+        match key {
+            SortKey::Text => indices.sort_by_cached_key(|i| self.tasks[*i].text.to_lowercase()),
+            SortKey::Created => {
+                indices.sort_by_key(|i| (self.tasks[*i].created_at, self.tasks[*i].id))
+            }
+            SortKey::Due => {
+                indices.sort_by_key(|i| (self.tasks[*i].due.is_none(), self.tasks[*i].due))
+            }
+            // Highest priority first.
+            SortKey::Priority => indices.sort_by_key(|i| Reverse(self.tasks[*i].priority)),
+        }
+    }
+
+    pub fn indices_filtered(&self, keep: impl Fn(&Task) -> bool) -> Vec<usize> {
+        self.indices()
+            .into_iter()
+            .filter(|i| keep(&self.tasks[*i]))
+            .collect()
+    }
+
+    pub fn indices_matching(&self, pattern: &Regex) -> Vec<usize> {
+        self.indices_filtered(|t| pattern.is_match(&t.text))
+    }
+
+    pub fn indices_tagged(&self, tags: &[&str]) -> Vec<usize> {
+        self.indices()
+            .into_iter()
+            .filter(|i| tags.iter().all(|tag| self.tasks[*i].has_tag(tag)))
+            .collect()
+    }
+
+    // Every distinct project with the number of tasks using it,
+    // sorted by name.
+    pub fn projects(&self) -> Vec<(String, usize)> {
+        count_tags(self.tasks.iter().flat_map(|t| t.projects.iter()))
+    }
+
+    pub fn contexts(&self) -> Vec<(String, usize)> {
+        count_tags(self.tasks.iter().flat_map(|t| t.contexts.iter()))
+    }
+
+    pub fn indices_due_today(&self, today: Date) -> Vec<usize> {
+        self.indices()
+            .into_iter()
+            .filter(|i| self.tasks[*i].is_due_today(today))
+            .collect()
+    }
+
+    pub fn indices_overdue(&self, today: Date) -> Vec<usize> {
+        self.indices()
+            .into_iter()
+            .filter(|i| self.tasks[*i].is_overdue(today))
+            .collect()
+    }
+
+    pub fn to_json(&self) -> String {
+        json::to_json(&self.tasks, &self.archive, &self.trash, self.next_id)
+    }
+
+    #[allow(clippy::wrong_self_convention)]
+    pub fn from_json(&mut self, text: &str) -> Result<(), Error> {
+        let document = json::from_json(text)?;
+
+        self.check_limit(document.tasks.len())?;
+        self.record();
+        self.tasks = document.tasks;
+        self.archive = document.archive;
+        self.trash = document.trash;
+        self.next_id = document.next_id.unwrap_or(1);
+        self.assign_ids();
+        self.arrange();
+        self.prune_blockers();
+        Ok(())
+    }
+
+    pub fn serialize(&self, format: Format) -> String {
+        match format {
+            Format::Text => self.to_text(),
+            Format::Json => self.to_json(),
+        }
+    }
+
+    pub fn deserialize(&mut self, text: &str, format: Format) -> Result<(), Error> {
+        match format {
+            Format::Text => self.from_text(text),
+            Format::Json => self.from_json(text),
+        }
+    }
+
+    #[allow(clippy::wrong_self_convention)]
+    pub fn from_text(&mut self, text: &str) -> Result<(), Error> {
+        let (next_id, text) = self::split_next_id(text)?;
+        let (text, trashed) = self::split_section(text, TRASH_HEADER);
+        let (text, archived) = self::split_section(text, ARCHIVE_HEADER);
+
+        let (tasks, parents) = self::parse_tasks(text)?;
+        let (archive, archive_parents) = self::parse_tasks(archived)?;
+        let (trash, trash_parents) = self::parse_trash(trashed)?;
+
+        self.check_limit(tasks.len())?;
+        self.record();
+        self.tasks = tasks;
+        self.archive = archive;
+        self.trash = trash;
+        self.next_id = next_id.unwrap_or(1);
+        self.assign_ids();
+
+        parents.iter().enumerate().for_each(|(i, parent)| {
+            self.tasks[i].parent = parent.map(|p| self.tasks[p].id);
+        });
+        archive_parents.iter().enumerate().for_each(|(i, parent)| {
+            self.archive[i].parent = parent.map(|p| self.archive[p].id);
+        });
+
+        // The first task of each entry already holds its old parent.
+        let trashed: Vec<u64> = self
+            .trash
+            .iter()
+            .flat_map(|e| &e.tasks)
+            .map(|t| t.id)
+            .collect();
+        self.trash
+            .iter_mut()
+            .flat_map(|e| &mut e.tasks)
+            .zip(trash_parents)
+            .for_each(|(task, parent)| {
+                if let Some(p) = parent {
+                    task.parent = Some(trashed[p]);
+                }
+            });
+
+        self.arrange();
+        self.prune_blockers();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::date;
+
+    // `text` without creation, completion and removal times, which change
+    // per run.
+    fn untimed(text: &str) -> String {
+        let times = Regex::new(r" (?:created|completed|removed):[^ ;]+").unwrap();
+        times.replace_all(text, "").into_owned()
+    }
+
+    #[test]
+    fn test_new() {
+        let list = List::new();
+        assert!(list.tasks.is_empty());
+        assert!(list.limit.is_none());
+    }
+
+    #[test]
+    fn test_add_unbounded() {
+        let mut list = List::new();
+
+        for i in 0..500 {
+            assert!(list.add(format!("test{i}")).is_ok());
+        }
+
+        assert!(list.tasks.len() == 500);
+    }
+
+    #[test]
+    fn test_add_limit() {
+        let mut list = List::new().with_limit(Some(2));
+        assert!(list.add("test1".to_string()).is_ok());
+        assert!(list.add("test2".to_string()).is_ok());
+
+        let result = list.add("test3".to_string());
+        assert!(matches!(result, Err(Error::CapacityExceeded)));
+        assert!(list.tasks.len() == 2);
+    }
+
+    #[test]
+    fn test_get() {
+        let mut list = List::new();
+        let _ = list.add("test".to_string());
+        let result = list.get(0);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_add() {
+        let mut list = List::new();
+        let result = list.add("test".to_string());
+        assert!(result.is_ok());
+        assert!(list.tasks.len() == 1);
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut list = List::new();
+        let _ = list.add("test".to_string());
+        assert!(list.tasks.len() == 1);
+        let result = list.remove(&[0]);
+        assert!(result.is_ok());
+        assert!(list.tasks.is_empty());
+    }
+
+    #[test]
+    fn test_remove_several() {
+        let mut list = List::new();
+        for text in ["a", "b", "c", "d", "e"] {
+            let _ = list.add(text.to_string());
+        }
+
+        assert!(matches!(list.remove(&[3, 0, 9]), Err(Error::ItemNotFound)));
+        assert!(list.tasks.len() == 5);
+
+        assert!(list.remove(&[3, 0, 1]).is_ok());
+        let texts: Vec<&str> = list.tasks.iter().map(|t| t.text.as_str()).collect();
+        assert_eq!(texts, ["c", "e"]);
+
+        assert!(list.undo().is_ok());
+        assert!(list.tasks.len() == 5);
+    }
+
+    #[test]
+    fn test_alter() {
+        let mut list = List::new();
+        let _ = list.add("test1".to_string());
+        assert!(list.get(0).unwrap().text == "test1");
+
+        let result = list.alter(0, "new_text".to_string());
+        assert!(result.is_ok());
+        assert!(list.get(0).unwrap().text == "new_text");
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut list = List::new();
+        let _ = list.add("test".to_string());
+
+        assert!(list.tasks.len() == 1);
+        list.clear();
+        assert!(list.tasks.is_empty());
+    }
+
+    #[test]
+    fn test_mark_done() {
+        let mut list = List::new();
+        let _ = list.add("test".to_string());
+
+        assert!(!list.get(0).unwrap().is_done());
+        let result = list.mark_done(&[0]);
+        assert!(result.is_ok());
+        assert!(list.get(0).unwrap().is_done());
+    }
+
+    #[test]
+    fn test_mark_undone() {
+        let mut list = List::new();
+        let _ = list.add("test".to_string());
+        let _ = list.mark_done(&[0]);
+
+        assert!(list.get(0).unwrap().is_done());
+        let result = list.mark_undone(&[0]);
+        assert!(result.is_ok());
+        assert!(!list.get(0).unwrap().is_done());
+    }
+
+    #[test]
+    fn test_mark_done_several() {
+        let mut list = List::new();
+        for text in ["a", "b", "c"] {
+            let _ = list.add(text.to_string());
+        }
+
+        assert!(matches!(list.mark_done(&[0, 3]), Err(Error::ItemNotFound)));
+        assert!(!list.get(0).unwrap().is_done());
+
+        assert!(list.mark_done(&[0, 2]).is_ok());
+        let done: Vec<bool> = list.tasks.iter().map(|t| t.is_done()).collect();
+        assert_eq!(done, [true, false, true]);
+
+        assert!(list.mark_undone(&[2]).is_ok());
+        assert!(!list.get(2).unwrap().is_done());
+
+        assert!(list.undo().is_ok());
+        assert!(list.undo().is_ok());
+        assert!(list.tasks.iter().all(|t| !t.is_done()));
+    }
+
+    #[test]
+    fn test_is_empty() {
+        let mut list = List::new();
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn test_to_text() {
+        let mut list = List::new();
+        let _ = list.add("test1".to_string());
+        let _ = list.add("test2".to_string());
+        let _ = list.mark_done(&[0]);
+        let result = untimed(&list.to_text());
+
+        assert!(result == "1) #1 [+] test1;\n2) #2 [ ] test2;\n");
+    }
+
+    #[test]
+    fn test_from_text() {
+        let mut list = List::new();
+
+        let mut expected = [
+            Task::new("test1".to_string()),
+            Task::new("test2".to_string()),
+        ];
+        expected[1].status = TaskStatus::Done;
+
+        let _ = list.from_text("1) [ ] test1;\n2) [+] test2;\n");
+
+        list.tasks.iter().enumerate().for_each(|(i, t)| {
+            assert!(t.text == expected.get(i).unwrap().text);
+            assert!(t.is_done() == expected.get(i).unwrap().is_done());
+        });
+    }
+
+    #[test]
+    fn test_status_round_trip() {
+        let mut list = List::new();
+        let text = "1) #1 [ ] a;\n2) #2 [>] b;\n3) #3 [~] c;\n4) #4 [+] d;\n5) #5 [-] e;\n";
+
+        assert!(list.from_text(text).is_ok());
+        let statuses: Vec<TaskStatus> = list.tasks.iter().map(|t| t.status).collect();
+        assert_eq!(statuses, TaskStatus::ALL);
+        assert_eq!(untimed(&list.to_text()), text);
+
+        assert!(list
+            .from_text("1) [ > ] a;\n2) [ + ] b;\n3) [] c;\n")
+            .is_ok());
+        assert_eq!(list.tasks[0].status, TaskStatus::Started);
+        assert_eq!(list.tasks[1].status, TaskStatus::Done);
+        assert_eq!(list.tasks[2].status, TaskStatus::Todo);
+
+        assert_eq!(list.indices_done(false), [0, 2]);
+        assert_eq!("Canceled".parse(), Ok(TaskStatus::Cancelled));
+        // Blocked is where dependencies leave a task, not a status.
+        assert_eq!("blocked".parse::<TaskStatus>(), Err(()));
+    }
+
+    #[test]
+    fn test_set_status() {
+        let mut list = List::new();
+        let _ = list.add("a".to_string());
+        let _ = list.mark_done(&[0]);
+        assert!(list.tasks[0].completed_at.is_some());
+
+        assert!(list.set_status(&[0], TaskStatus::Cancelled).is_ok());
+        assert!(!list.tasks[0].is_open() && !list.tasks[0].is_done());
+        assert!(list.tasks[0].completed_at.is_none());
+        assert!(matches!(
+            list.set_status(&[1], TaskStatus::Started),
+            Err(Error::ItemNotFound)
+        ));
+    }
+
+    #[test]
+    fn test_from_text_empty() {
+        let mut list = List::new();
+        let _ = list.add("test".to_string());
+
+        let result = list.from_text("\n");
+        assert!(result.is_ok());
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn test_from_text_limit() {
+        let mut list = List::new().with_limit(Some(1));
+        let _ = list.add("test".to_string());
+
+        let result = list.from_text("1) [ ] test1;\n2) [+] test2;\n");
+        assert!(matches!(result, Err(Error::CapacityExceeded)));
+        assert!(list.get(0).unwrap().text == "test");
+    }
+
+    #[test]
+    fn test_format_from_path() {
+        assert!(matches!(Format::from_path("list.json"), Format::Json));
+        assert!(matches!(Format::from_path("LIST.JSON"), Format::Json));
+        assert!(matches!(Format::from_path("list.txt"), Format::Text));
+        assert!(matches!(Format::from_path("list"), Format::Text));
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let mut list = List::new();
+        let _ = list.add("buy [milk]; eggs".to_string());
+        let _ = list.mark_done(&[0]);
+
+        let mut loaded = List::new();
+        let result = loaded.deserialize(&list.serialize(Format::Json), Format::Json);
+
+        assert!(result.is_ok());
+        assert!(loaded.get(0).unwrap().text == "buy [milk]; eggs");
+        assert!(loaded.get(0).unwrap().is_done());
+    }
+
+    #[test]
+    fn test_from_json_limit() {
+        let mut list = List::new();
+        let _ = list.add("test1".to_string());
+        let _ = list.add("test2".to_string());
+        let text = list.to_json();
+
+        let mut limited = List::new().with_limit(Some(1));
+        let result = limited.from_json(&text);
+        assert!(matches!(result, Err(Error::CapacityExceeded)));
+    }
+
+    #[test]
+    fn test_set_priority() {
+        let mut list = List::new();
+        let _ = list.add("test".to_string());
+
+        assert_eq!(list.get(0).unwrap().priority, Priority::None);
+        let result = list.set_priority(0, Priority::High);
+        assert!(result.is_ok());
+        assert_eq!(list.get(0).unwrap().priority, Priority::High);
+
+        let result = list.set_priority(1, Priority::High);
+        assert!(matches!(result, Err(Error::ItemNotFound)));
+    }
+
+    #[test]
+    fn test_to_text_priority() {
+        let mut list = List::new();
+        let _ = list.add("test1".to_string());
+        let _ = list.add("test2".to_string());
+        let _ = list.set_priority(1, Priority::High);
+
+        assert!(untimed(&list.to_text()) == "1) #1 [ ] test1;\n2) #2 [ ] (H) test2;\n");
+    }
+
+    #[test]
+    fn test_sort_indices_by_priority() {
+        let mut list = List::new();
+        let _ = list.add("test1".to_string());
+        let _ = list.add("test2".to_string());
+        let _ = list.add("test3".to_string());
+        let _ = list.set_priority(1, Priority::Low);
+        let _ = list.set_priority(2, Priority::High);
+
+        let mut indices = list.indices();
+        list.sort_indices(&mut indices, SortKey::Priority);
+        assert_eq!(indices, vec![2, 1, 0]);
+        assert!(
+            list.to_view(&indices, date::today())
+                == "3) #3 [ ] (H) test3;\n2) #2 [ ] (L) test2;\n1) #1 [ ] test1;\n"
+        );
+    }
+
+    #[test]
+    fn test_from_text_priority() {
+        let mut list = List::new();
+        let result = list.from_text("1) [+] (M) test1;\n2) [ ] test2;\n3) [ ] (X) test3;\n");
+
+        assert!(result.is_ok());
+        assert_eq!(list.get(0).unwrap().priority, Priority::Medium);
+        assert!(list.get(0).unwrap().text == "test1");
+        assert!(list.get(0).unwrap().is_done());
+        assert_eq!(list.get(1).unwrap().priority, Priority::None);
+        assert!(list.get(2).unwrap().text == "(X) test3");
+    }
+
+    #[test]
+    fn test_set_due() {
+        let mut list = List::new();
+        let _ = list.add("test".to_string());
+        let due = Date::from_ymd(2026, 11, 1);
+
+        assert!(list.set_due(0, due).is_ok());
+        assert_eq!(list.get(0).unwrap().due, due);
+        assert!(untimed(&list.to_text()) == "1) #1 [ ] test due:2026-11-01;\n");
+
+        assert!(list.set_due(0, None).is_ok());
+        assert!(list.get(0).unwrap().due.is_none());
+        assert!(matches!(list.set_due(1, due), Err(Error::ItemNotFound)));
+    }
+
+    #[test]
+    fn test_from_text_due() {
+        let mut list = List::new();
+        let result =
+            list.from_text("1) [ ] (H) test1 due:2026-11-01;\n2) [ ] test2 due:someday;\n");
+
+        assert!(result.is_ok());
+        assert!(list.get(0).unwrap().text == "test1");
+        assert_eq!(list.get(0).unwrap().priority, Priority::High);
+        assert_eq!(list.get(0).unwrap().due, Date::from_ymd(2026, 11, 1));
+        assert!(list.get(1).unwrap().text == "test2 due:someday");
+        assert!(list.get(1).unwrap().due.is_none());
+    }
+
+    #[test]
+    fn test_to_view_due_flags() {
+        let today = Date::from_ymd(2026, 10, 18).unwrap();
+        let mut list = List::new();
+        let _ = list.add("test1".to_string());
+        let _ = list.add("test2".to_string());
+        let _ = list.add("test3".to_string());
+        let _ = list.add("test4".to_string());
+        let _ = list.set_due(0, today.add_days(-1));
+        let _ = list.set_due(1, Some(today));
+        let _ = list.set_due(2, today.add_days(1));
+        let _ = list.set_due(3, today.add_days(-1));
+        let _ = list.mark_done(&[3]);
+
+        assert!(
+            list.to_view(&list.indices(), today)
+                == "1) #1 [ ] test1 due:2026-10-17 (overdue);
+2) #2 [ ] test2 due:2026-10-18 (due today);
+3) #3 [ ] test3 due:2026-10-19;
+4) #4 [+] test4 due:2026-10-17;
+"
+        );
+        assert_eq!(list.indices_overdue(today), vec![0]);
+        assert_eq!(list.indices_due_today(today), vec![1]);
+    }
+
+    #[test]
+    fn test_extract_tags() {
+        let (text, projects, contexts) = extract_tags("call  mom +family @phone +family @ +");
+        assert_eq!(text, "call  mom @ +");
+        assert_eq!(projects, vec!["family"]);
+        assert_eq!(contexts, vec!["phone"]);
+
+        let (text, projects, contexts) = extract_tags("call\tmom\t+family\n@phone");
+        assert_eq!(text, "call mom");
+        assert_eq!(projects, vec!["family"]);
+        assert_eq!(contexts, vec!["phone"]);
+
+        let (text, projects, contexts) = extract_tags("  plain  text ");
+        assert_eq!(text, "  plain  text ");
+        assert!(projects.is_empty());
+        assert!(contexts.is_empty());
+    }
+
+    #[test]
+    fn test_add_tags() {
+        let mut list = List::new();
+        let _ = list.add("write report +work @office".to_string());
+
+        let task = list.get(0).unwrap();
+        assert!(task.text == "write report");
+        assert_eq!(task.projects, vec!["work"]);
+        assert_eq!(task.contexts, vec!["office"]);
+        assert!(untimed(&list.to_text()) == "1) #1 [ ] write report +work @office;\n");
+    }
+
+    #[test]
+    fn test_alter_tags() {
+        let mut list = List::new();
+        let _ = list.add("write report +work".to_string());
+        let _ = list.alter(0, "read book @home".to_string());
+
+        let task = list.get(0).unwrap();
+        assert!(task.text == "read book");
+        assert!(task.projects.is_empty());
+        assert_eq!(task.contexts, vec!["home"]);
+    }
+
+    #[test]
+    fn test_from_text_tags() {
+        let mut list = List::new();
+        let result = list.from_text("1) [ ] (H) write report +work @office due:2026-11-01;\n");
+
+        assert!(result.is_ok());
+        let task = list.get(0).unwrap();
+        assert!(task.text == "write report");
+        assert_eq!(task.priority, Priority::High);
+        assert_eq!(task.projects, vec!["work"]);
+        assert_eq!(task.contexts, vec!["office"]);
+        assert_eq!(task.due, Date::from_ymd(2026, 11, 1));
+    }
+
+    #[test]
+    fn test_indices_tagged() {
+        let mut list = List::new();
+        let _ = list.add("test1 +work @office".to_string());
+        let _ = list.add("test2 +home".to_string());
+        let _ = list.add("test3 +work @home".to_string());
+
+        assert_eq!(list.indices_tagged(&["+work"]), vec![0, 2]);
+        assert_eq!(list.indices_tagged(&["+work", "@home"]), vec![2]);
+        assert_eq!(list.indices_tagged(&["@garden"]), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn test_sort_indices() {
+        let today = Date::from_ymd(2026, 10, 18).unwrap();
+        let mut list = List::new();
+        let _ = list.add("b".to_string());
+        let _ = list.add("C".to_string());
+        let _ = list.add("a".to_string());
+        let _ = list.set_due(0, today.add_days(2));
+        let _ = list.set_due(2, Some(today));
+        let _ = list.set_priority(1, Priority::High);
+        let _ = list.remove(&[0]);
+        let _ = list.add("b".to_string());
+
+        let sorted = |key: SortKey| {
+            let mut indices = list.indices();
+            list.sort_indices(&mut indices, key);
+            indices
+        };
+
+        assert_eq!(sorted(SortKey::Text), vec![1, 2, 0]);
+        assert_eq!(sorted(SortKey::Created), vec![0, 1, 2]);
+        assert_eq!(sorted(SortKey::Due), vec![1, 0, 2]);
+        assert_eq!(sorted(SortKey::Priority), vec![0, 1, 2]);
+        assert_eq!("Due".parse::<SortKey>(), Ok(SortKey::Due));
+        assert!("size".parse::<SortKey>().is_err());
+    }
+
+    #[test]
+    fn test_indices_done() {
+        let mut list = List::new();
+        let _ = list.add("a".to_string());
+        let _ = list.add("b".to_string());
+        let _ = list.mark_done(&[1]);
+
+        assert_eq!(list.indices_done(true), vec![1]);
+        assert_eq!(list.indices_done(false), vec![0]);
+    }
+
+    #[test]
+    fn test_indices_matching() {
+        let today = Date::from_ymd(2026, 10, 18).unwrap();
+        let mut list = List::new();
+        let _ = list.add("Buy milk".to_string());
+        let _ = list.add("call mum".to_string());
+        let _ = list.add("milk the cow, more milk".to_string());
+
+        let pattern = Regex::new("(?i)milk").unwrap();
+
+        assert_eq!(list.indices_matching(&pattern), vec![0, 2]);
+        assert_eq!(
+            list.to_highlighted_view(&[2], today, false, &pattern),
+            "3) #3 [ ] \x1b[1;4mmilk\x1b[0m the cow, more \x1b[1;4mmilk\x1b[0m;\n"
+        );
+
+        let pattern = Regex::new("l*").unwrap();
+
+        assert_eq!(
+            list.to_highlighted_view(&[1], today, false, &pattern),
+            "2) #2 [ ] ca\x1b[1;4mll\x1b[0m mum;\n"
+        );
+    }
+
+    #[test]
+    fn test_projects_contexts() {
+        let mut list = List::new();
+        let _ = list.add("test1 +work @office".to_string());
+        let _ = list.add("test2 +home".to_string());
+        let _ = list.add("test3 +work @home".to_string());
+
+        assert_eq!(
+            list.projects(),
+            vec![("home".to_string(), 1), ("work".to_string(), 2)]
+        );
+        assert_eq!(
+            list.contexts(),
+            vec![("home".to_string(), 1), ("office".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn test_add_ids() {
+        let mut list = List::new();
+        let _ = list.add("test1".to_string());
+        let _ = list.add("test2".to_string());
+        let _ = list.remove(&[0]);
+        let _ = list.add("test3".to_string());
+
+        assert_eq!(list.get(0).unwrap().id, 2);
+        assert_eq!(list.get(1).unwrap().id, 3);
+        assert_eq!(list.position_of(3), Some(1));
+        assert_eq!(list.position_of(1), None);
+    }
+
+    #[test]
+    fn test_from_text_ids() {
+        let mut list = List::new();
+        let result = list.from_text("1) #5 [ ] test1;\n2) [ ] test2;\n3) #5 [+] test3;\n");
+
+        assert!(result.is_ok());
+        assert_eq!(list.get(0).unwrap().id, 5);
+        assert_eq!(list.get(1).unwrap().id, 6);
+        assert_eq!(list.get(2).unwrap().id, 7);
+
+        let _ = list.add("test4".to_string());
+        assert_eq!(list.get(3).unwrap().id, 8);
+    }
+
+    #[test]
+    fn test_ids_round_trip() {
+        let mut list = List::new();
+        let _ = list.add("test1".to_string());
+        let _ = list.add("test2".to_string());
+        let _ = list.remove(&[0]);
+
+        let mut loaded = List::new();
+        let _ = loaded.from_text(&list.to_text());
+        assert_eq!(loaded.get(0).unwrap().id, 2);
+
+        let mut loaded = List::new();
+        let _ = loaded.from_json(&list.to_json());
+        assert_eq!(loaded.get(0).unwrap().id, 2);
+    }
+
+    // a, with subtasks a1 (which has a1x) and a2, then b.
+    fn tree() -> List {
+        let mut list = List::new();
+        let _ = list.add("a".to_string());
+        let _ = list.add("b".to_string());
+        let _ = list.add_subtask(0, "a1".to_string());
+        let _ = list.add_subtask(0, "a2".to_string());
+        let _ = list.add_subtask(1, "a1x".to_string());
+
+        list.tasks.iter_mut().for_each(|t| t.created_at = None);
+        list
+    }
+
+    #[test]
+    fn test_add_subtask() {
+        let list = tree();
+        let texts: Vec<&str> = list.tasks.iter().map(|t| t.text.as_str()).collect();
+
+        assert_eq!(texts, ["a", "a1", "a1x", "a2", "b"]);
+        assert_eq!(list.tasks[1].parent, Some(1));
+        assert_eq!(list.tasks[2].parent, Some(3));
+        assert_eq!(list.tasks[3].parent, Some(1));
+        assert!(list.tasks[4].parent.is_none());
+        assert!(matches!(
+            List::new().add_subtask(0, "a".to_string()),
+            Err(Error::ItemNotFound)
+        ));
+    }
+
+    #[test]
+    fn test_position_at() {
+        let list = tree();
+
+        assert_eq!(list.position_at(&[1]), Some(0));
+        assert_eq!(list.position_at(&[2]), Some(4));
+        assert_eq!(list.position_at(&[1, 1]), Some(1));
+        assert_eq!(list.position_at(&[1, 1, 1]), Some(2));
+        assert_eq!(list.position_at(&[1, 2]), Some(3));
+        assert_eq!(list.position_at(&[3]), None);
+        assert_eq!(list.position_at(&[2, 1]), None);
+        assert_eq!(list.position_at(&[0]), None);
+    }
+
+    #[test]
+    fn test_subtasks() {
+        let list = tree();
+
+        assert_eq!(list.subtasks(0), [1, 2, 3]);
+        assert_eq!(list.subtasks(1), [2]);
+        assert!(list.subtasks(4).is_empty());
+        assert!(list.subtasks(9).is_empty());
+    }
 
-impl List {
-    pub fn new() -> Self {
-        Self {
-            tasks: Vec::with_capacity(TASKS_LIST_MAX_CAPACITY),
-        }
+    #[test]
+    fn test_remove_subtree() {
+        let mut list = tree();
+        assert!(list.remove(&[1]).is_ok());
+
+        let texts: Vec<&str> = list.tasks.iter().map(|t| t.text.as_str()).collect();
+        assert_eq!(texts, ["a", "a2", "b"]);
     }
 
-    pub fn get(&mut self, index: usize) -> Result<&Task, Error> {
-        match self.tasks.get(index) {
-            Some(task) => Ok(&task),
-            None => Err(Error::ItemNotFound),
-        }
+    #[test]
+    fn test_to_text_tree() {
+        assert_eq!(
+            tree().to_text(),
+            "1) #1 [ ] a;\n  1.1) #3 [ ] a1;\n    1.1.1) #5 [ ] a1x;\n  1.2) #4 [ ] a2;\n2) #2 [ ] b;\n"
+        );
     }
 
-    pub fn add(&mut self, text: String) -> Result<(), Error> {
-        if self.tasks.len() < self.tasks.capacity() {
-            self.tasks.push(Task {
-                text,
-                is_done: false,
-            });
-            return Ok(());
-        }
+    #[test]
+    fn test_from_text_tree() {
+        let mut list = List::new();
+        assert!(list.from_text(&tree().to_text()).is_ok());
+        assert_eq!(list.to_text(), tree().to_text());
 
-        Err(Error::CapacityExceeded)
+        // A missing parent makes a top-level task.
+        assert!(list
+            .from_text("1) [ ] a;\n2.1) [ ] b;\n1.1) [ ] c;\n")
+            .is_ok());
+        assert_eq!(
+            list.to_text(),
+            "1) #1 [ ] a;\n  1.1) #3 [ ] c;\n2) #2 [ ] b;\n"
+        );
     }
 
-    pub fn remove(&mut self, index: usize) -> Result<(), Error> {
-        if index < self.tasks.len() {
-            self.tasks.remove(index);
-            return Ok(());
-        }
+    #[test]
+    fn test_json_tree_round_trip() {
+        let mut list = List::new();
+        assert!(list.from_json(&tree().to_json()).is_ok());
+        assert_eq!(list.to_text(), tree().to_text());
+    }
+
+    #[test]
+    fn test_from_json_arranges_tree() {
+        let json = r#"{"version": 2, "tasks": [
+            {"id": 3, "text": "c", "is_done": false, "parent": 1},
+            {"id": 1, "text": "a", "is_done": false},
+            {"id": 4, "text": "d", "is_done": false, "parent": 9},
+            {"id": 5, "text": "e", "is_done": false, "parent": 6},
+            {"id": 6, "text": "f", "is_done": false, "parent": 5}
+        ]}"#;
 
-        Err(Error::ItemNotFound)
+        let mut list = List::new();
+        assert!(list.from_json(json).is_ok());
+        assert_eq!(
+            list.to_text(),
+            "1) #1 [ ] a;\n  1.1) #3 [ ] c;\n2) #4 [ ] d;\n3) #5 [ ] e;\n  3.1) #6 [ ] f;\n"
+        );
     }
 
-    pub fn alter(&mut self, index: usize, next_text: String) -> Result<(), Error> {
-        match self.tasks.get_mut(index) {
-            Some(task) => {
-                task.text = next_text;
-                Ok(())
-            }
-            None => Err(Error::ItemNotFound),
+    #[test]
+    fn test_block() {
+        let mut list = List::new();
+
+        for text in ["a", "b", "c"] {
+            let _ = list.add(text.to_string());
         }
+
+        assert!(list.block(2, 1).is_ok());
+        assert!(list.block(1, 0).is_ok());
+        assert!(list.block(1, 0).is_ok());
+        assert_eq!(list.tasks[1].blocked_by, [1]);
+        assert_eq!(list.blockers(2), [1]);
+
+        assert!(matches!(list.block(0, 2), Err(Error::DependencyCycle)));
+        assert!(matches!(list.block(0, 0), Err(Error::DependencyCycle)));
+        assert!(matches!(list.block(0, 3), Err(Error::ItemNotFound)));
+
+        let _ = list.mark_done(&[1]);
+        assert!(list.blockers(2).is_empty());
+
+        assert!(list.unblock(2, Some(1)).is_ok());
+        assert!(list.tasks[2].blocked_by.is_empty());
+        assert!(list.unblock(1, None).is_ok());
+        assert!(list.tasks[1].blocked_by.is_empty());
     }
 
-    pub fn clear(&mut self) {
-        self.tasks.clear();
+    #[test]
+    fn test_remove_blocker() {
+        let mut list = List::new();
+        let _ = list.add("a".to_string());
+        let _ = list.add("b".to_string());
+        let _ = list.block(1, 0);
+
+        assert!(list.remove(&[0]).is_ok());
+        assert!(list.tasks[0].blocked_by.is_empty());
     }
 
-    pub fn mark_done(&mut self, index: usize) -> Result<(), Error> {
-        match self.tasks.get_mut(index) {
-            Some(task) => {
-                task.is_done = true;
-                Ok(())
-            }
-            None => Err(Error::ItemNotFound),
-        }
+    #[test]
+    fn test_indices_actionable() {
+        let mut list = tree();
+        let _ = list.add("c".to_string());
+        let _ = list.block(5, 4);
+        let _ = list.mark_done(&[3]);
+
+        // a waits for a1, which waits for a1x; c waits for b.
+        assert_eq!(list.indices_actionable(), [2, 4]);
     }
 
-    pub fn mark_undone(&mut self, index: usize) -> Result<(), Error> {
-        match self.tasks.get_mut(index) {
-            Some(task) => {
-                task.is_done = false;
-                Ok(())
-            }
-            None => Err(Error::ItemNotFound),
+    #[test]
+    fn test_to_view_blocked() {
+        let mut list = List::new();
+        let _ = list.add("a".to_string());
+        let _ = list.add("b".to_string());
+        let _ = list.block(1, 0);
+
+        let today = date::today();
+        assert_eq!(
+            list.to_view(&list.indices(), today),
+            "1) #1 [ ] a;\n2) #2 [ ] b after:#1 (blocked);\n"
+        );
+
+        let _ = list.mark_done(&[0]);
+        assert_eq!(list.to_view(&[1], today), "2) #2 [ ] b after:#1;\n");
+    }
+
+    #[test]
+    fn test_blockers_round_trip() {
+        let mut list = List::new();
+        let _ = list.add("a +work".to_string());
+        let _ = list.add("b".to_string());
+        let _ = list.add("c".to_string());
+        let _ = list.block(0, 1);
+        let _ = list.block(0, 2);
+        let _ = list.set_due(0, Some(date::today()));
+
+        for format in ["list.txt", "list.json"] {
+            let mut loaded = List::new();
+            let text = list.serialize(Format::from_path(format));
+
+            assert!(loaded.deserialize(&text, Format::from_path(format)).is_ok());
+            assert_eq!(loaded.tasks[0].blocked_by, [2, 3]);
+            assert_eq!(loaded.tasks[0].text, "a");
+            assert_eq!(loaded.tasks[0].projects, ["work"]);
         }
     }
 
-    pub fn is_empty(&mut self) -> bool {
-        self.tasks.is_empty()
+    #[test]
+    fn test_token_text_round_trip() {
+        let mut list = List::new();
+        let _ = list.add("a".to_string());
+        let _ = list.add("wait after:#1".to_string());
+        let _ = list.add("ask \\due:2026-10-18 repeat:daily".to_string());
+        let _ = list.add("(H) not a priority".to_string());
+        let _ = list.add("(L) low".to_string());
+        let _ = list.set_priority(4, Priority::Low);
+
+        let text = list.to_text();
+        assert!(text.contains("2) #2 [ ] wait \\after:#1 created:"));
+        assert!(text.contains("3) #3 [ ] ask \\\\due:2026-10-18 \\repeat:daily created:"));
+        assert!(text.contains("4) #4 [ ] \\(H) not a priority created:"));
+        assert!(text.contains("5) #5 [ ] (L) \\(L) low created:"));
+
+        let mut loaded = List::new();
+        assert!(loaded.from_text(&text).is_ok());
+        assert_eq!(loaded.tasks[1].text, "wait after:#1");
+        assert!(loaded.tasks[1].blocked_by.is_empty());
+        assert_eq!(loaded.tasks[2].text, "ask \\due:2026-10-18 repeat:daily");
+        assert!(loaded.tasks[2].recurrence.is_none());
+        assert_eq!(loaded.tasks[3].text, "(H) not a priority");
+        assert_eq!(loaded.tasks[3].priority, Priority::None);
+        assert_eq!(loaded.tasks[4].text, "(L) low");
+        assert_eq!(loaded.tasks[4].priority, Priority::Low);
     }
 
-    pub fn to_text(&self) -> String {
-        let mut result = String::new();
+    #[test]
+    fn test_from_text_prunes_blockers() {
+        let mut list = List::new();
+        let text = "1) #1 [ ] a after:#2;\n2) #2 [ ] b after:#1 after:#9;\n3) #3 [ ] c after:#3;\n";
 
-        self.tasks.iter().enumerate().for_each(|(i, t)| {
-            let status = match t.is_done {
-                true => DONE_MARK,
-                false => UNDONE_MARK,
-            };
+        assert!(list.from_text(text).is_ok());
+        assert_eq!(list.tasks[0].blocked_by, [2]);
+        assert!(list.tasks[1].blocked_by.is_empty());
+        assert!(list.tasks[2].blocked_by.is_empty());
+    }
 
-            result.push_str(&format!("{}) {} {};\n", i + 1, status, t.text));
-        });
+    #[test]
+    fn test_mark_done_recurring() {
+        let today = date::today();
+        let mut list = tree();
+        let _ = list.set_due(0, Some(today));
+        let _ = list.set_recurrence(0, Some(Recurrence::Daily));
+        let _ = list.set_recurrence(4, Some(Recurrence::After(3)));
 
-        result
+        assert!(list.mark_done(&[4, 0]).is_ok());
+
+        let texts: Vec<&str> = list.tasks.iter().map(|t| t.text.as_str()).collect();
+        assert_eq!(texts, ["a", "a1", "a1x", "a2", "a", "b", "b"]);
+
+        let (done, next) = (&list.tasks[0], &list.tasks[4]);
+        assert!(done.is_done() && done.recurrence.is_none());
+        assert!(!next.is_done());
+        assert_eq!(next.id, 6);
+        assert_eq!(next.due, today.add_days(1));
+        assert_eq!(next.recurrence, Some(Recurrence::Daily));
+        assert_eq!(list.tasks[6].id, 7);
+        assert_eq!(list.tasks[6].due, today.add_days(3));
+
+        // Already done tasks do not repeat again.
+        assert!(list.mark_done(&[0]).is_ok());
+        assert_eq!(list.tasks.len(), 7);
+
+        assert!(list.undo().is_ok());
+        assert!(list.undo().is_ok());
+        assert_eq!(list.tasks.len(), 5);
+        assert_eq!(list.tasks[0].recurrence, Some(Recurrence::Daily));
     }
 
-    pub fn from_text(&mut self, text: &str) -> Result<(), Error> {
-        let mut result: Vec<Task> = Vec::new();
-        let re = Regex::new(r"(\[\s*\+?\s*\])(([^;\[\]])+)(;)").unwrap();
+    #[test]
+    fn test_mark_done_recurring_subtask() {
+        let mut list = List::new();
+        let _ = list.add("parent".to_string());
+        let _ = list.add_subtask(0, "child".to_string());
+        let _ = list.set_recurrence(0, Some(Recurrence::Daily));
+        let _ = list.set_recurrence(1, Some(Recurrence::Daily));
 
-        if re.is_match(&text) {
-            re.captures_iter(text).for_each(|c| {
-                let check = utils::trim_str(c.get(1).unwrap().into());
-                let text = utils::trim_str(c.get(2).unwrap().into());
+        assert!(list.mark_done(&[0, 1]).is_ok());
 
-                result.push(Task {
-                    text: text.to_string(),
-                    is_done: check == DONE_MARK,
-                });
-            });
+        let tasks: Vec<(u64, Option<u64>)> = list.tasks.iter().map(|t| (t.id, t.parent)).collect();
+        assert_eq!(tasks, [(1, None), (2, Some(1)), (4, Some(1)), (3, None)]);
+        assert_eq!(list.subtasks(0).len(), 2);
+    }
 
-            self.tasks = result;
-            Ok(())
-        } else {
-            Err(Error::InvalidPattern)
-        }
+    #[test]
+    fn test_mark_done_recurring_limit() {
+        let mut list = List::new().with_limit(Some(1));
+        let _ = list.add("a".to_string());
+        let _ = list.set_recurrence(0, Some(Recurrence::Daily));
+
+        assert!(matches!(list.mark_done(&[0]), Err(Error::CapacityExceeded)));
+        assert!(!list.tasks[0].is_done());
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn test_recurrence_round_trip() {
+        let mut list = List::new();
+        let _ = list.add("report +work".to_string());
+        let _ = list.add("b".to_string());
+        let _ = list.block(0, 1);
+        let _ = list.set_due(0, Some(date::today()));
+        let _ = list.set_recurrence(0, Some(Recurrence::Weekly(vec![0, 3])));
+
+        assert!(list
+            .to_text()
+            .contains("report +work after:#2 repeat:weekly:mon,thu due:"));
+
+        for format in ["list.txt", "list.json"] {
+            let mut loaded = List::new();
+            let text = list.serialize(Format::from_path(format));
+
+            assert!(loaded.deserialize(&text, Format::from_path(format)).is_ok());
+            assert_eq!(loaded.tasks[0].text, "report");
+            assert_eq!(
+                loaded.tasks[0].recurrence,
+                Some(Recurrence::Weekly(vec![0, 3]))
+            );
+            assert_eq!(loaded.tasks[0].blocked_by, [2]);
+            assert_eq!(loaded.tasks[0].due, Some(date::today()));
+        }
+    }
 
     #[test]
-    fn test_new() {
-        let list = List::new();
-        assert!(list.tasks.len() == 0);
-        assert!(list.tasks.capacity() == TASKS_LIST_MAX_CAPACITY);
+    fn test_timestamps() {
+        let before = Timestamp::now();
+        let mut list = List::new();
+        let _ = list.add("a".to_string());
+        let _ = list.add("b".to_string());
+
+        let created = list.tasks[0].created_at.unwrap();
+        assert!(before <= created && created <= Timestamp::now());
+        assert!(list.tasks[0].completed_at.is_none());
+
+        let _ = list.mark_done(&[0]);
+        let completed = list.tasks[0].completed_at.unwrap();
+        assert!(created <= completed);
+
+        // Completing again keeps the first time.
+        list.tasks[0].completed_at = Some(Timestamp::from_seconds(0));
+        let _ = list.mark_done(&[0, 1]);
+        assert_eq!(list.tasks[0].completed_at, Some(Timestamp::from_seconds(0)));
+        assert!(list.tasks[1].completed_at.is_some());
+
+        let _ = list.mark_undone(&[0]);
+        assert!(list.tasks[0].completed_at.is_none());
+        assert_eq!(list.tasks[0].created_at, Some(created));
     }
 
     #[test]
-    fn test_get() {
+    fn test_timestamps_round_trip() {
         let mut list = List::new();
-        let _ = list.add("test".to_string());
-        let result = list.get(0);
-        assert!(result.is_ok());
+        let _ = list.add("a due".to_string());
+        let _ = list.set_due(0, Some(date::today()));
+        list.tasks[0].created_at = Some(Timestamp::from_seconds(1_792_332_185));
+        list.tasks[0].completed_at = Some(Timestamp::from_seconds(1_792_418_585));
+        list.tasks[0].status = TaskStatus::Done;
+
+        assert!(list
+            .to_text()
+            .ends_with(" created:2026-10-18T14:03:05Z completed:2026-10-19T14:03:05Z;\n"));
+
+        for format in ["list.txt", "list.json"] {
+            let mut loaded = List::new();
+            let text = list.serialize(Format::from_path(format));
+
+            assert!(loaded.deserialize(&text, Format::from_path(format)).is_ok());
+            assert_eq!(loaded.tasks[0].text, "a due");
+            assert_eq!(loaded.tasks[0].due, Some(date::today()));
+            assert_eq!(loaded.tasks[0].created_at, list.tasks[0].created_at);
+            assert_eq!(loaded.tasks[0].completed_at, list.tasks[0].completed_at);
+        }
     }
 
     #[test]
-    fn test_add() {
+    fn test_to_long_view() {
         let mut list = List::new();
-        let result = list.add("test".to_string());
-        assert!(result.is_ok());
-        assert!(list.tasks.len() == 1);
+        let _ = list.add("a".to_string());
+        list.tasks[0].created_at = Some(Timestamp::from_seconds(1_792_332_185));
+
+        let today = date::today();
+        assert_eq!(list.to_view(&[0], today), "1) #1 [ ] a;\n");
+        assert_eq!(
+            list.to_long_view(&[0], today),
+            "1) #1 [ ] a created:2026-10-18T14:03:05Z;\n"
+        );
     }
 
     #[test]
-    fn test_remove() {
+    fn test_sort_indices_created() {
         let mut list = List::new();
-        let _ = list.add("test".to_string());
-        assert!(list.tasks.len() == 1);
-        let result = list.remove(0);
-        assert!(result.is_ok());
-        assert!(list.tasks.len() == 0);
+
+        for text in ["a", "b", "c"] {
+            let _ = list.add(text.to_string());
+        }
+
+        list.tasks[0].created_at = Some(Timestamp::from_seconds(20));
+        list.tasks[1].created_at = Some(Timestamp::from_seconds(10));
+        list.tasks[2].created_at = None;
+
+        let mut indices = list.indices();
+        list.sort_indices(&mut indices, SortKey::Created);
+        assert_eq!(indices, [2, 1, 0]);
     }
 
     #[test]
-    fn test_alter() {
+    fn test_undo_redo() {
         let mut list = List::new();
         let _ = list.add("test1".to_string());
-        assert!(list.get(0).unwrap().text == "test1");
+        let _ = list.add("test2".to_string());
+        let _ = list.mark_done(&[0]);
+        let _ = list.alter(1, "new-test".to_string());
 
-        let result = list.alter(0, "new_text".to_string());
-        assert!(result.is_ok());
-        assert!(list.get(0).unwrap().text == "new_text".to_string());
+        assert!(list.undo().is_ok());
+        assert!(list.get(1).unwrap().text == "test2");
+        assert!(list.undo().is_ok());
+        assert!(!list.get(0).unwrap().is_done());
+        assert!(list.redo().is_ok());
+        assert!(list.get(0).unwrap().is_done());
+        assert!(list.redo().is_ok());
+        assert!(list.get(1).unwrap().text == "new-test");
+        assert!(matches!(list.redo(), Err(Error::NothingToRedo)));
     }
 
     #[test]
-    fn test_clear() {
+    fn test_undo_all_mutations() {
         let mut list = List::new();
-        let _ = list.add("test".to_string());
 
-        assert!(list.tasks.len() == 1);
+        assert!(matches!(list.undo(), Err(Error::NothingToUndo)));
+
+        let _ = list.add("test1".to_string());
+        let _ = list.add("test2".to_string());
+        let _ = list.mark_done(&[0]);
+        let _ = list.mark_undone(&[0]);
+        let _ = list.set_priority(0, Priority::High);
+        let _ = list.set_due(0, Date::from_ymd(2026, 11, 1));
+        let _ = list.remove(&[1]);
         list.clear();
-        assert!(list.tasks.is_empty());
+
+        for _ in 0..8 {
+            assert!(list.undo().is_ok());
+        }
+
+        assert!(list.is_empty());
+        assert!(matches!(list.undo(), Err(Error::NothingToUndo)));
     }
 
     #[test]
-    fn test_mark_done() {
+    fn test_undo_ids() {
         let mut list = List::new();
-        let _ = list.add("test".to_string());
+        let _ = list.add("test1".to_string());
+        let _ = list.undo();
+        let _ = list.add("test2".to_string());
 
-        assert!(!list.get(0).unwrap().is_done);
-        let result = list.mark_done(0);
-        assert!(result.is_ok());
-        assert!(list.get(0).unwrap().is_done);
+        assert_eq!(list.get(0).unwrap().id, 1);
     }
 
     #[test]
-    fn test_mark_undone() {
+    fn test_undo_from_text() {
         let mut list = List::new();
         let _ = list.add("test".to_string());
-        let _ = list.mark_done(0);
+        let _ = list.from_text("1) [ ] test1;\n2) [+] test2;\n");
 
-        assert!(list.get(0).unwrap().is_done);
-        let result = list.mark_undone(0);
-        assert!(result.is_ok());
-        assert!(!list.get(0).unwrap().is_done);
+        assert!(list.undo().is_ok());
+        assert!(list.get(0).unwrap().text == "test");
+        assert!(list.get(1).is_err());
     }
 
     #[test]
-    fn test_is_empty() {
+    fn test_failed_mutation_not_recorded() {
         let mut list = List::new();
-        assert!(list.is_empty());
+
+        assert!(list.mark_done(&[0]).is_err());
+        assert!(list.from_text("invalid").is_err());
+        assert!(matches!(list.undo(), Err(Error::NothingToUndo)));
     }
 
     #[test]
-    fn test_to_text() {
-        let mut list = List::new();
+    fn test_history_depth() {
+        let mut list = List::new().with_history(1);
         let _ = list.add("test1".to_string());
         let _ = list.add("test2".to_string());
-        let _ = list.mark_done(0);
-        let result = list.to_text();
 
-        assert!(result == "1) [+] test1;\n2) [ ] test2;\n");
+        assert!(list.undo().is_ok());
+        assert!(matches!(list.undo(), Err(Error::NothingToUndo)));
+        assert!(list.get(0).unwrap().text == "test1");
     }
 
     #[test]
-    fn test_from_text() {
+    fn test_clear_history() {
         let mut list = List::new();
+        let _ = list.add("test".to_string());
+        list.clear_history();
 
-        let expected = vec![
-            Task {
-                text: "test1".to_string(),
-                is_done: false,
-            },
-            Task {
-                text: "test2".to_string(),
-                is_done: true,
-            },
-        ];
+        assert!(matches!(list.undo(), Err(Error::NothingToUndo)));
+    }
 
-        let _ = list.from_text("1) [ ] test1;\n2) [+] test2;\n");
+    #[test]
+    fn test_archive() {
+        let mut list = tree();
+        assert!(list.mark_done(&[0, 1, 2, 3]).is_ok());
+        assert_eq!(list.indices_archivable(None), [0]);
 
-        list.tasks.iter().enumerate().for_each(|(i, t)| {
-            assert!(t.text == expected.get(i).unwrap().text);
-            assert!(t.is_done == expected.get(i).unwrap().is_done);
-        });
+        assert!(list.archive(&[0]).is_ok());
+        let texts: Vec<&str> = list.tasks.iter().map(|t| t.text.as_str()).collect();
+        assert_eq!(texts, ["b"]);
+        assert_eq!(
+            untimed(&list.archived().to_text()),
+            "1) #1 [+] a;\n  1.1) #3 [+] a1;\n    1.1.1) #5 [+] a1x;\n  1.2) #4 [+] a2;\n"
+        );
+
+        // Subtasks come back on their own as top-level tasks.
+        assert!(list.unarchive(1).is_ok());
+        assert_eq!(
+            untimed(&list.to_text()),
+            "1) #2 [ ] b;\n2) #3 [+] a1;\n  2.1) #5 [+] a1x;\narchive:\n1) #1 [+] a;\n  1.1) #4 [+] a2;\n"
+        );
+
+        assert!(list.undo().is_ok());
+        assert_eq!(list.tasks.len(), 1);
+        assert!(list.undo().is_ok());
+        assert!(list.archived().is_empty());
+        assert!(matches!(list.unarchive(0), Err(Error::ItemNotFound)));
+    }
+
+    #[test]
+    fn test_indices_archivable() {
+        let mut list = tree();
+        let _ = list.mark_done(&[0, 2, 4]);
+
+        // a still has open subtasks, and a1x stays with its parent.
+        assert_eq!(list.indices_archivable(None), [4]);
+
+        let _ = list.mark_done(&[1, 3]);
+        assert_eq!(list.indices_archivable(None), [0, 4]);
+
+        list.tasks.iter_mut().for_each(|t| t.completed_at = None);
+        list.tasks[2].completed_at = Some(Timestamp::from_seconds(1_792_332_185));
+
+        // All of a must be old enough.
+        let today = Date::from_ymd(2026, 10, 18).unwrap();
+        assert_eq!(list.indices_archivable(Some(today)), [4]);
+        assert_eq!(list.indices_archivable(today.add_days(1)), [0, 4]);
+    }
+
+    #[test]
+    fn test_archive_round_trip() {
+        let mut list = tree();
+        let _ = list.mark_done(&[4]);
+        let _ = list.archive(&[4]);
+
+        for path in ["list.txt", "list.json"] {
+            let text = list.serialize(Format::from_path(path));
+            let mut loaded = List::new();
+            assert!(loaded.deserialize(&text, Format::from_path(path)).is_ok());
+            assert_eq!(loaded.to_text(), list.to_text());
+
+            // Archived ids stay taken.
+            let _ = loaded.add("c".to_string());
+            assert_eq!(loaded.tasks.last().unwrap().id, 6);
+        }
+
+        let mut loaded = List::new();
+        assert!(loaded.from_text("archive:\n1) #4 [+] x;\n").is_ok());
+        assert!(loaded.is_empty());
+        assert_eq!(loaded.archived().tasks[0].id, 4);
+    }
+
+    #[test]
+    fn test_unarchive_limit() {
+        let mut list = List::new().with_limit(Some(1));
+        let _ = list.add("a".to_string());
+        let _ = list.mark_done(&[0]);
+        let _ = list.archive(&[0]);
+        let _ = list.add("b".to_string());
+
+        assert!(matches!(list.unarchive(0), Err(Error::CapacityExceeded)));
+    }
+
+    #[test]
+    fn test_move_between_lists() {
+        let mut list = tree();
+        assert!(list.block(1, 3).is_ok());
+        assert!(list.block(4, 1).is_ok());
+
+        let mut other = List::new();
+        let _ = other.add("x".to_string());
+        other.tasks[0].created_at = None;
+
+        let tasks = list.copy_subtree(0).unwrap();
+        assert!(other.adopt(tasks).is_ok());
+        assert!(list.release(0).is_ok());
+
+        assert_eq!(
+            other.to_text(),
+            "1) #1 [ ] x;\n2) #2 [ ] a;\n  2.1) #3 [ ] a1 after:#5;\n    2.1.1) #4 [ ] a1x;\n  2.2) #5 [ ] a2;\n"
+        );
+        assert_eq!(list.to_text(), "next:#6\n1) #2 [ ] b;\n");
+
+        assert!(matches!(list.undo(), Err(Error::NothingToUndo)));
+        assert_eq!(list.tasks.len(), 1);
+        assert!(matches!(list.copy_subtree(5), Err(Error::ItemNotFound)));
+        assert!(matches!(list.release(5), Err(Error::ItemNotFound)));
+    }
+
+    #[test]
+    fn test_adopt_limit() {
+        let list = tree();
+        let mut other = List::new().with_limit(Some(2));
+
+        assert!(matches!(
+            other.adopt(list.copy_subtree(0).unwrap()),
+            Err(Error::CapacityExceeded)
+        ));
+        assert!(other.adopt(list.copy_subtree(4).unwrap()).is_ok());
+        assert_eq!(other.blank().limit, Some(2));
+    }
+
+    #[test]
+    fn test_trash() {
+        let mut list = tree();
+        assert!(list.remove(&[1]).is_ok());
+
+        assert_eq!(
+            untimed(&list.to_trash_view()),
+            "1) #3 [ ] a1;\n  1.1) #5 [ ] a1x;\n"
+        );
+        assert!(list.to_trash_view().contains(" removed:"));
+
+        assert!(list.restore_from_trash(0).is_ok());
+        assert_eq!(list.to_text(), tree().to_text());
+        assert!(list.to_trash_view().is_empty());
+        assert!(matches!(
+            list.restore_from_trash(0),
+            Err(Error::ItemNotFound)
+        ));
+    }
+
+    #[test]
+    fn test_trash_restore_without_parent() {
+        let mut list = tree();
+        let _ = list.remove(&[1]);
+        let _ = list.remove(&[0]);
+
+        assert_eq!(
+            untimed(&list.to_trash_view()),
+            "1) #1 [ ] a;\n  1.1) #4 [ ] a2;\n2) #3 [ ] a1;\n  2.1) #5 [ ] a1x;\n"
+        );
+
+        // a1 left from under a, which is gone too.
+        assert!(list.restore_from_trash(1).is_ok());
+        assert_eq!(
+            list.to_text().lines().take(3).collect::<Vec<&str>>(),
+            ["1) #2 [ ] b;", "2) #3 [ ] a1;", "  2.1) #5 [ ] a1x;"]
+        );
+    }
+
+    #[test]
+    fn test_trash_clear_and_undo() {
+        let mut list = tree();
+        list.clear();
+
+        assert!(list.is_empty());
+        assert_eq!(list.trash.len(), 2);
+
+        assert!(list.undo().is_ok());
+        assert_eq!(list.to_text(), tree().to_text());
+
+        let _ = list.redo();
+        assert!(list.restore_from_trash(1).is_ok());
+        assert!(list.restore_from_trash(0).is_ok());
+        assert_eq!(list.to_text(), tree().to_text());
+
+        let _ = list.remove(&[0]);
+        list.empty_trash();
+        assert!(list.trash.is_empty());
+    }
+
+    #[test]
+    fn test_trash_size() {
+        let mut list = List::new().with_trash(2);
+
+        for text in ["a", "b", "c"] {
+            let _ = list.add(text.to_string());
+        }
+        for _ in 0..3 {
+            let _ = list.remove(&[0]);
+        }
+
+        assert_eq!(
+            untimed(&list.to_trash_view()),
+            "1) #3 [ ] c;\n2) #2 [ ] b;\n"
+        );
+
+        let mut list = List::new().with_trash(0);
+        let _ = list.add("a".to_string());
+        let _ = list.remove(&[0]);
+        assert!(list.to_trash_view().is_empty());
+    }
+
+    #[test]
+    fn test_ids_not_reused() {
+        let mut list = List::new();
+        let _ = list.add("a".to_string());
+        let _ = list.add("b".to_string());
+        let _ = list.add("c".to_string());
+        let _ = list.remove(&[1, 2]);
+        list.empty_trash();
+        list.tasks[0].created_at = None;
+
+        assert_eq!(list.to_text(), "next:#4\n1) #1 [ ] a;\n");
+
+        for path in ["list.txt", "list.json"] {
+            let mut loaded = List::new();
+            let text = list.serialize(Format::from_path(path));
+            assert!(loaded.deserialize(&text, Format::from_path(path)).is_ok());
+
+            let _ = loaded.add("d".to_string());
+            assert_eq!(loaded.get(1).unwrap().id, 4);
+        }
+
+        let mut loaded = List::new();
+        assert!(loaded.from_text("next:#2\n1) #5 [ ] a;\n").is_ok());
+        let _ = loaded.add("b".to_string());
+        assert_eq!(loaded.get(1).unwrap().id, 6);
+
+        assert!(loaded.from_text("next:#4\n").is_ok());
+        assert!(loaded.is_empty());
+        assert!(matches!(
+            loaded.from_text("next:4\n1) [ ] a;\n"),
+            Err(Error::InvalidPattern)
+        ));
+    }
+
+    #[test]
+    fn test_trash_restores_dependencies() {
+        let mut list = tree();
+        let _ = list.block(4, 1);
+        let _ = list.block(4, 2);
+        let _ = list.block(3, 0);
+        let _ = list.remove(&[1]);
+
+        assert!(list.tasks[2].blocked_by.is_empty());
+        assert_eq!(list.tasks[1].blocked_by, [1]);
+
+        let text = untimed(&list.to_text());
+        assert!(text.ends_with(
+            "trash:\n1) #3 [ ] a1 at:1 under:#1 waiting:#2>#3 waiting:#2>#5;\n  1.1) #5 [ ] a1x;\n"
+        ));
+
+        for path in ["list.txt", "list.json"] {
+            let text = list.serialize(Format::from_path(path));
+            let mut loaded = List::new();
+            assert!(loaded.deserialize(&text, Format::from_path(path)).is_ok());
+
+            assert!(loaded.restore_from_trash(0).is_ok());
+            assert_eq!(loaded.tasks[4].blocked_by, [3, 5]);
+            assert_eq!(loaded.tasks[3].blocked_by, [1]);
+        }
+    }
+
+    #[test]
+    fn test_trash_round_trip() {
+        let mut list = tree();
+        let _ = list.mark_done(&[4]);
+        let _ = list.archive(&[4]);
+        let _ = list.remove(&[1]);
+        let _ = list.remove(&[0]);
+
+        let text = untimed(&list.to_text());
+        assert!(text.ends_with("trash:\n1) #1 [ ] a at:0;\n  1.1) #4 [ ] a2;\n2) #3 [ ] a1 at:1 under:#1;\n  2.1) #5 [ ] a1x;\n"));
+
+        for path in ["list.txt", "list.json"] {
+            let text = list.serialize(Format::from_path(path));
+            let mut loaded = List::new();
+            assert!(loaded.deserialize(&text, Format::from_path(path)).is_ok());
+            assert_eq!(loaded.to_text(), list.to_text());
+
+            assert!(loaded.restore_from_trash(0).is_ok());
+            assert!(loaded.restore_from_trash(0).is_ok());
+            assert_eq!(
+                untimed(&loaded.to_text()),
+                "1) #1 [ ] a;\n  1.1) #3 [ ] a1;\n    1.1.1) #5 [ ] a1x;\n  1.2) #4 [ ] a2;\narchive:\n1) #2 [+] b;\n"
+            );
+        }
+
+        let mut loaded = List::new();
+        assert!(matches!(
+            loaded.from_text("trash:\n1) #1 [ ] a;\n"),
+            Err(Error::InvalidPattern)
+        ));
     }
 }
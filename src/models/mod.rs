@@ -1,2 +1,6 @@
+pub mod history;
+pub mod json;
 pub mod list;
+pub mod priority;
+pub mod recurrence;
 pub mod state;
@@ -0,0 +1,71 @@
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    #[default]
+    None,
+    Low,
+    Medium,
+    High,
+}
+
+impl Priority {
+    pub fn mark(&self) -> Option<&'static str> {
+        match self {
+            Priority::None => None,
+            Priority::Low => Some("(L)"),
+            Priority::Medium => Some("(M)"),
+            Priority::High => Some("(H)"),
+        }
+    }
+
+    pub fn from_mark(mark: &str) -> Option<Self> {
+        [Priority::Low, Priority::Medium, Priority::High]
+            .into_iter()
+            .find(|p| p.mark() == Some(mark))
+    }
+}
+
+impl FromStr for Priority {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "none" | "n" => Ok(Priority::None),
+            "low" | "l" => Ok(Priority::Low),
+            "medium" | "m" => Ok(Priority::Medium),
+            "high" | "h" => Ok(Priority::High),
+            _ => Err(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!("high".parse::<Priority>(), Ok(Priority::High));
+        assert_eq!("M".parse::<Priority>(), Ok(Priority::Medium));
+        assert_eq!("low".parse::<Priority>(), Ok(Priority::Low));
+        assert_eq!("none".parse::<Priority>(), Ok(Priority::None));
+        assert!("urgent".parse::<Priority>().is_err());
+    }
+
+    #[test]
+    fn test_order() {
+        assert!(Priority::High > Priority::Medium);
+        assert!(Priority::Medium > Priority::Low);
+        assert!(Priority::Low > Priority::None);
+    }
+
+    #[test]
+    fn test_mark() {
+        assert_eq!(Priority::None.mark(), None);
+        assert_eq!(Priority::from_mark("(H)"), Some(Priority::High));
+        assert_eq!(Priority::from_mark("(X)"), None);
+    }
+}
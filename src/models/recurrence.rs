@@ -0,0 +1,270 @@
+use crate::date::{self, Date};
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+const DAILY: &str = "daily";
+const WEEKLY: &str = "weekly";
+const MONTHLY: &str = "monthly";
+const AFTER: &str = "after";
+const DAYS_SUFFIX: char = 'd';
+
+// When a task comes back after it is done, written as `daily`, `weekly`
+// (every 7 days), `weekly:mon,thu`, `monthly:15` or `after:3d` (3 days
+// after it was done).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub enum Recurrence {
+    Daily,
+    // Weekdays with Monday as 0, none for the weekday of the last due date.
+    Weekly(Vec<u32>),
+    // Clamped to the length of shorter months.
+    Monthly(u32),
+    After(u32),
+}
+
+impl Recurrence {
+    // The following date on the schedule, strictly after `from`, or
+    // `None` past the last date that can be written.
+    fn step(&self, from: Date) -> Option<Date> {
+        match self {
+            Recurrence::Daily => from.add_days(1),
+            Recurrence::Weekly(days) if days.is_empty() => from.add_days(7),
+            Recurrence::Weekly(days) => (1..=7)
+                .filter_map(|n| from.add_days(n))
+                .find(|d| days.contains(&d.weekday())),
+            Recurrence::Monthly(day) => match from.on_day(*day) {
+                this_month if this_month > from => Some(this_month),
+                _ => Some(from.add_months(1)?.on_day(*day)),
+            },
+            Recurrence::After(days) => from.add_days(*days as i64),
+        }
+    }
+
+    // The due date of the occurrence after one due on `due` and done on
+    // `today`. Calendar rules go on from the due date (or today without
+    // one) to the first date after today, so that finishing late does not
+    // leave the next occurrence overdue already.
+    pub fn next(&self, due: Option<Date>, today: Date) -> Option<Date> {
+        if let Recurrence::After(..) = self {
+            return self.step(today);
+        }
+
+        let mut next = self.step(due.unwrap_or(today))?;
+
+        while next <= today {
+            next = self.step(next)?;
+        }
+
+        Some(next)
+    }
+}
+
+// A positive number written with digits only.
+fn parse_count(text: &str) -> Result<u32, ()> {
+    match text.parse::<u32>() {
+        Ok(count) if count > 0 && text.bytes().all(|b| b.is_ascii_digit()) => Ok(count),
+        _ => Err(()),
+    }
+}
+
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Recurrence::Daily => write!(f, "{DAILY}"),
+            Recurrence::Weekly(days) if days.is_empty() => write!(f, "{WEEKLY}"),
+            Recurrence::Weekly(days) => {
+                let names: Vec<&str> = days.iter().map(|d| date::weekday_name(*d)).collect();
+                write!(f, "{WEEKLY}:{}", names.join(","))
+            }
+            Recurrence::Monthly(day) => write!(f, "{MONTHLY}:{day}"),
+            Recurrence::After(days) => write!(f, "{AFTER}:{days}{DAYS_SUFFIX}"),
+        }
+    }
+}
+
+impl FromStr for Recurrence {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.to_lowercase();
+
+        match s.split_once(':') {
+            None if s == DAILY => Ok(Recurrence::Daily),
+            None if s == WEEKLY => Ok(Recurrence::Weekly(Vec::new())),
+            Some((WEEKLY, days)) => {
+                let mut days = days
+                    .split(',')
+                    .map(date::parse_weekday)
+                    .collect::<Option<Vec<u32>>>()
+                    .ok_or(())?;
+
+                days.sort();
+                days.dedup();
+                Ok(Recurrence::Weekly(days))
+            }
+            Some((MONTHLY, day)) => match self::parse_count(day)? {
+                day @ 1..=31 => Ok(Recurrence::Monthly(day)),
+                _ => Err(()),
+            },
+            Some((AFTER, days)) => {
+                let days = days.strip_suffix(DAYS_SUFFIX).unwrap_or(days);
+                self::parse_count(days).map(Recurrence::After)
+            }
+            _ => Err(()),
+        }
+    }
+}
+
+impl From<Recurrence> for String {
+    fn from(recurrence: Recurrence) -> Self {
+        recurrence.to_string()
+    }
+}
+
+impl TryFrom<String> for Recurrence {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value
+            .parse::<Recurrence>()
+            .map_err(|_| format!("invalid recurrence: {value}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i64, month: u32, day: u32) -> Date {
+        Date::from_ymd(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!("daily".parse::<Recurrence>(), Ok(Recurrence::Daily));
+        assert_eq!("Weekly".parse(), Ok(Recurrence::Weekly(vec![])));
+        assert_eq!(
+            "weekly:thu,mon,mon".parse(),
+            Ok(Recurrence::Weekly(vec![0, 3]))
+        );
+        assert_eq!("monthly:31".parse(), Ok(Recurrence::Monthly(31)));
+        assert_eq!("after:3d".parse(), Ok(Recurrence::After(3)));
+        assert_eq!("after:10".parse(), Ok(Recurrence::After(10)));
+
+        for invalid in [
+            "",
+            "hourly",
+            "weekly:",
+            "weekly:mon,",
+            "weekly:funday",
+            "monthly",
+            "monthly:0",
+            "monthly:32",
+            "monthly:+5",
+            "after:0d",
+            "after:d",
+            "after:-1d",
+            "daily:2",
+        ] {
+            assert!(invalid.parse::<Recurrence>().is_err(), "{invalid:?}");
+        }
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        for text in [
+            "daily",
+            "weekly",
+            "weekly:mon,thu",
+            "monthly:15",
+            "after:3d",
+        ] {
+            assert_eq!(text.parse::<Recurrence>().unwrap().to_string(), text);
+        }
+    }
+
+    #[test]
+    fn test_serde() {
+        let json = serde_json::to_string(&Recurrence::Weekly(vec![4])).unwrap();
+        assert_eq!(json, "\"weekly:fri\"");
+        assert_eq!(
+            serde_json::from_str::<Recurrence>(&json).unwrap(),
+            Recurrence::Weekly(vec![4])
+        );
+        assert!(serde_json::from_str::<Recurrence>("\"sometimes\"").is_err());
+    }
+
+    #[test]
+    fn test_next() {
+        // Saturday
+        let today = date(2026, 10, 17);
+        let due = Some(date(2026, 10, 20));
+
+        assert_eq!(Recurrence::Daily.next(due, today), Some(date(2026, 10, 21)));
+        assert_eq!(
+            Recurrence::Daily.next(None, today),
+            Some(date(2026, 10, 18))
+        );
+        assert_eq!(
+            Recurrence::Weekly(vec![]).next(due, today),
+            Some(date(2026, 10, 27))
+        );
+        assert_eq!(
+            Recurrence::Weekly(vec![0, 3]).next(due, today),
+            Some(date(2026, 10, 22))
+        );
+        assert_eq!(
+            Recurrence::Weekly(vec![0, 3]).next(None, today),
+            Some(date(2026, 10, 19))
+        );
+        assert_eq!(
+            Recurrence::Monthly(15).next(due, today),
+            Some(date(2026, 11, 15))
+        );
+        assert_eq!(
+            Recurrence::Monthly(25).next(due, today),
+            Some(date(2026, 10, 25))
+        );
+        assert_eq!(
+            Recurrence::After(3).next(due, today),
+            Some(date(2026, 10, 20))
+        );
+    }
+
+    #[test]
+    fn test_next_skips_past_dates() {
+        let today = date(2026, 10, 17);
+        let overdue = Some(date(2026, 9, 1));
+
+        assert_eq!(
+            Recurrence::Daily.next(overdue, today),
+            Some(date(2026, 10, 18))
+        );
+        assert_eq!(
+            Recurrence::Weekly(vec![]).next(overdue, today),
+            Some(date(2026, 10, 20))
+        );
+        assert_eq!(
+            Recurrence::Monthly(1).next(overdue, today),
+            Some(date(2026, 11, 1))
+        );
+    }
+
+    #[test]
+    fn test_next_monthly_short_months() {
+        let today = date(2026, 1, 1);
+
+        assert_eq!(
+            Recurrence::Monthly(31).next(Some(date(2026, 1, 31)), today),
+            Some(date(2026, 2, 28))
+        );
+        assert_eq!(
+            Recurrence::Monthly(31).next(Some(date(2026, 2, 28)), today),
+            Some(date(2026, 3, 31))
+        );
+        assert_eq!(
+            Recurrence::Monthly(30).next(Some(date(2026, 2, 28)), today),
+            Some(date(2026, 3, 30))
+        );
+    }
+}
@@ -1,3 +1,8 @@
+use crate::{
+    config::{Config, DEFAULT_BACKUPS, DEFAULT_LIST_NAME},
+    storage,
+};
+
 #[derive(Debug, PartialEq)]
 pub enum Status {
     NeedPlainText,
@@ -8,6 +13,19 @@ pub struct State<'a> {
     pub command: Option<&'a str>,
     pub status: Option<Status>,
     pub task_index: Option<usize>,
+    pub task_indices: Vec<usize>,
+    pub data_path: Option<String>,
+    // The named list in use, or `None` for the default one.
+    pub list: Option<String>,
+    // Off while the list in use could not be read, so it is never
+    // overwritten.
+    pub autosave: bool,
+    pub backups: usize,
+    pub config_path: Option<String>,
+    pub views: Vec<(String, String)>,
+    // Whether search matches are highlighted, only when writing to a
+    // terminal.
+    pub highlight: bool,
 }
 
 impl<'a> State<'a> {
@@ -16,19 +34,65 @@ impl<'a> State<'a> {
             command: None,
             status: None,
             task_index: None,
+            task_indices: vec![],
+            data_path: None,
+            list: None,
+            autosave: true,
+            backups: DEFAULT_BACKUPS,
+            config_path: None,
+            views: vec![],
+            highlight: false,
         }
     }
 
+    pub fn with_data_path(mut self, path: &str) -> Self {
+        self.data_path = Some(path.to_string());
+        self
+    }
+
+    pub fn with_backups(mut self, backups: usize) -> Self {
+        self.backups = backups;
+        self
+    }
+
+    pub fn with_views(mut self, config: &Config) -> Self {
+        self.config_path = config.path.clone();
+        self.views = config.views.clone();
+        self
+    }
+
+    pub fn with_highlight(mut self, highlight: bool) -> Self {
+        self.highlight = highlight;
+        self
+    }
+
+    pub fn list_name(&self) -> &str {
+        self.list.as_deref().unwrap_or(DEFAULT_LIST_NAME)
+    }
+
+    // The file the list in use is saved to.
+    pub fn list_path(&self) -> Option<String> {
+        let path = self.data_path.as_ref()?;
+        Some(storage::list_path(path, self.list.as_deref()))
+    }
+
     pub fn set(&mut self, command: &'a str, status: Status, task_index: Option<usize>) {
         self.command = Some(command);
         self.status = Some(status);
         self.task_index = task_index;
     }
 
+    // Like `set`, for commands that act on several tasks at once.
+    pub fn set_many(&mut self, command: &'a str, status: Status, task_indices: Vec<usize>) {
+        self.set(command, status, None);
+        self.task_indices = task_indices;
+    }
+
     pub fn reset(&mut self) {
         self.command = None;
         self.status = None;
         self.task_index = None;
+        self.task_indices.clear();
     }
 }
 
@@ -43,6 +107,57 @@ mod tests {
         assert!(state.command.is_none());
         assert!(state.status.is_none());
         assert!(state.task_index.is_none());
+        assert!(state.data_path.is_none());
+        assert!(state.backups == DEFAULT_BACKUPS);
+    }
+
+    #[test]
+    fn test_with_settings() {
+        let state = State::new()
+            .with_data_path("list.json")
+            .with_backups(2)
+            .with_highlight(true);
+
+        assert!(state.data_path == Some("list.json".to_string()));
+        assert!(state.backups == 2);
+        assert!(state.highlight);
+    }
+
+    #[test]
+    fn test_reset_keeps_settings() {
+        let mut state = State::new().with_data_path("list.json");
+
+        state.set("test", Status::NeedPlainText, None);
+        state.reset();
+
+        assert!(state.data_path == Some("list.json".to_string()));
+    }
+
+    #[test]
+    fn test_list_path() {
+        let mut state = State::new();
+        assert!(state.list_path().is_none());
+
+        state.data_path = Some("list.json".to_string());
+        assert!(state.list_name() == DEFAULT_LIST_NAME);
+        assert!(state.list_path() == Some("list.json".to_string()));
+
+        state.list = Some("work".to_string());
+        assert!(state.list_name() == "work");
+        assert!(state.list_path() == Some("list.work.json".to_string()));
+    }
+
+    #[test]
+    fn test_with_views() {
+        let config = Config {
+            views: vec![("work".to_string(), "tag:work".to_string())],
+            path: Some("todocli.conf".to_string()),
+            ..Config::default()
+        };
+        let state = State::new().with_views(&config);
+
+        assert!(state.config_path == Some("todocli.conf".to_string()));
+        assert!(state.views == config.views);
     }
 
     #[test]
@@ -82,4 +197,19 @@ mod tests {
         assert!(state.status.is_none());
         assert!(state.task_index.is_none());
     }
+
+    #[test]
+    fn test_set_many() {
+        let mut state = State::new();
+
+        state.set_many("test", Status::NeedConfirmation, vec![1, 3]);
+
+        assert!(state.command == Some("test"));
+        assert!(state.task_index.is_none());
+        assert!(state.task_indices == [1, 3]);
+
+        state.reset();
+
+        assert!(state.task_indices.is_empty());
+    }
 }
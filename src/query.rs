@@ -0,0 +1,548 @@
+use crate::{
+    date::{self, Date},
+    models::{
+        list::{List, Task, TaskStatus},
+        priority::Priority,
+    },
+};
+use std::{cmp::Ordering, fmt};
+
+const K_AND: &str = "and";
+const K_OR: &str = "or";
+const K_NOT: &str = "not";
+const V_PENDING: &str = "pending";
+const V_BLOCKED: &str = "blocked";
+// Parentheses and `not`s nested deeper than this are rejected, so a
+// hostile query cannot exhaust the stack.
+const MAX_DEPTH: usize = 32;
+
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    UnexpectedEnd,
+    UnexpectedToken(String),
+    UnterminatedQuote,
+    MissingOperator(String),
+    InvalidOperator(String),
+    UnknownField(String),
+    InvalidValue(String),
+    TooDeep,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::UnexpectedEnd => write!(f, "query ends too early"),
+            Error::UnexpectedToken(token) => write!(f, "unexpected {token:?} in query"),
+            Error::UnterminatedQuote => write!(f, "unterminated \" quote in query"),
+            Error::MissingOperator(term) => write!(f, "{term:?} needs an operator like : or <"),
+            Error::InvalidOperator(term) => write!(f, "{term:?} cannot be compared that way"),
+            Error::UnknownField(field) => write!(f, "unknown field {field:?}"),
+            Error::InvalidValue(term) => write!(f, "{term:?} has an invalid value"),
+            Error::TooDeep => write!(f, "query nested more than {MAX_DEPTH} levels deep"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Op {
+    // Longer operators first so `<=` is not read as `<`.
+    const SYMBOLS: [(&'static str, Op); 7] = [
+        ("<=", Op::Le),
+        (">=", Op::Ge),
+        ("!=", Op::Ne),
+        (":", Op::Eq),
+        ("=", Op::Eq),
+        ("<", Op::Lt),
+        (">", Op::Gt),
+    ];
+
+    fn holds(&self, ordering: Ordering) -> bool {
+        match self {
+            Op::Eq => ordering == Ordering::Equal,
+            Op::Ne => ordering != Ordering::Equal,
+            Op::Lt => ordering == Ordering::Less,
+            Op::Le => ordering != Ordering::Greater,
+            Op::Gt => ordering == Ordering::Greater,
+            Op::Ge => ordering != Ordering::Less,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Condition {
+    // Any status that still needs work.
+    Pending,
+    // Not a status of its own: open and waiting for open tasks.
+    Blocked,
+    Status(TaskStatus),
+    // `+project`, `@context` or a bare name matching either.
+    Tag(String),
+    // Lowercased, matched anywhere in the text.
+    Text(String),
+    Priority(Op, Priority),
+    // The date expression is kept as written so `+7d` stays relative
+    // to the day the query runs, `None` compares against no due date.
+    Due(Op, Option<String>),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Expr {
+    Is(Condition),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    // `list` holds `task` and the tasks it may be waiting for.
+    pub fn matches(&self, task: &Task, list: &List, today: Date) -> bool {
+        match self {
+            Expr::Is(condition) => condition.matches(task, list, today),
+            Expr::Not(expr) => !expr.matches(task, list, today),
+            Expr::And(left, right) => {
+                left.matches(task, list, today) && right.matches(task, list, today)
+            }
+            Expr::Or(left, right) => {
+                left.matches(task, list, today) || right.matches(task, list, today)
+            }
+        }
+    }
+}
+
+impl Condition {
+    fn matches(&self, task: &Task, list: &List, today: Date) -> bool {
+        match self {
+            Condition::Pending => task.is_open(),
+            Condition::Blocked => list.is_blocked(task),
+            Condition::Status(status) => task.status == *status,
+            Condition::Tag(tag) if tag.starts_with(['+', '@']) => task.has_tag(tag),
+            Condition::Tag(name) => task.projects.contains(name) || task.contexts.contains(name),
+            Condition::Text(text) => task.text.to_lowercase().contains(text),
+            Condition::Priority(op, priority) => op.holds(task.priority.cmp(priority)),
+            Condition::Due(op, None) => (*op == Op::Eq) == task.due.is_none(),
+            Condition::Due(op, Some(expression)) => {
+                match (task.due, date::parse(expression, today)) {
+                    (Some(due), Some(date)) => op.holds(due.cmp(&date)),
+                    _ => false,
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    // Quoted words are never keywords.
+    Word(String, bool),
+}
+
+fn lex(input: &str) -> Result<Vec<Token>, Error> {
+    let mut tokens = vec![];
+    let mut chars = input.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            '(' | ')' => {
+                chars.next();
+                tokens.push(if c == '(' { Token::Open } else { Token::Close });
+            }
+            _ if c.is_whitespace() => {
+                chars.next();
+            }
+            _ => {
+                let mut word = String::new();
+                let mut quoted = false;
+
+                while let Some(c) = chars.next_if(|c| !c.is_whitespace() && !"()".contains(*c)) {
+                    if c != '"' {
+                        word.push(c);
+                        continue;
+                    }
+
+                    quoted = true;
+
+                    loop {
+                        match chars.next() {
+                            Some('"') => break,
+                            Some(q) => word.push(q),
+                            None => return Err(Error::UnterminatedQuote),
+                        }
+                    }
+                }
+
+                tokens.push(Token::Word(word, quoted));
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+// Splits `field<op>value` and builds the condition it stands for.
+fn term(word: &str) -> Result<Expr, Error> {
+    let missing = || Error::MissingOperator(word.to_string());
+    let invalid = || Error::InvalidValue(word.to_string());
+    let not_comparable = || Error::InvalidOperator(word.to_string());
+
+    let at = word.find([':', '=', '!', '<', '>']).ok_or_else(missing)?;
+    let (field, rest) = word.split_at(at);
+    let (op, value) = Op::SYMBOLS
+        .iter()
+        .find_map(|(symbol, op)| rest.strip_prefix(symbol).map(|v| (*op, v)))
+        .ok_or_else(missing)?;
+
+    if value.is_empty() {
+        return Err(invalid());
+    }
+
+    let condition = match field.to_lowercase().as_str() {
+        "status" => match value.to_lowercase().as_str() {
+            V_PENDING => Condition::Pending,
+            V_BLOCKED => Condition::Blocked,
+            other => Condition::Status(other.parse().map_err(|_| invalid())?),
+        },
+        "tag" => Condition::Tag(value.to_string()),
+        "project" => Condition::Tag(format!("+{value}")),
+        "context" => Condition::Tag(format!("@{value}")),
+        "text" => Condition::Text(value.to_lowercase()),
+        "prio" | "priority" => {
+            let priority = value.parse::<Priority>().map_err(|_| invalid())?;
+            return Ok(Expr::Is(Condition::Priority(op, priority)));
+        }
+        "due" if value == "none" => match op {
+            Op::Eq | Op::Ne => return Ok(Expr::Is(Condition::Due(op, None))),
+            _ => return Err(not_comparable()),
+        },
+        "due" => {
+            date::parse(value, date::today()).ok_or_else(invalid)?;
+            return Ok(Expr::Is(Condition::Due(op, Some(value.to_string()))));
+        }
+        _ => return Err(Error::UnknownField(field.to_string())),
+    };
+
+    match op {
+        Op::Eq => Ok(Expr::Is(condition)),
+        Op::Ne => Ok(Expr::Not(Box::new(Expr::Is(condition)))),
+        _ => Err(not_comparable()),
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Word(w, false)) if w.eq_ignore_ascii_case(keyword))
+    }
+
+    fn keyword(&mut self, keyword: &str) -> bool {
+        let found = self.is_keyword(keyword);
+        self.position += found as usize;
+        found
+    }
+
+    // Runs `parse` one nesting level deeper.
+    fn nested(&mut self, parse: fn(&mut Self) -> Result<Expr, Error>) -> Result<Expr, Error> {
+        if self.depth == MAX_DEPTH {
+            return Err(Error::TooDeep);
+        }
+
+        self.depth += 1;
+        let expr = parse(self);
+        self.depth -= 1;
+        expr
+    }
+
+    fn or(&mut self) -> Result<Expr, Error> {
+        let mut left = self.and()?;
+
+        while self.keyword(K_OR) {
+            left = Expr::Or(Box::new(left), Box::new(self.and()?));
+        }
+
+        Ok(left)
+    }
+
+    // Terms written next to each other are joined by an implicit `and`.
+    fn and(&mut self) -> Result<Expr, Error> {
+        let mut left = self.not()?;
+
+        loop {
+            let implicit = match self.peek() {
+                Some(Token::Open) => true,
+                Some(Token::Word(..)) => !self.is_keyword(K_OR) && !self.is_keyword(K_AND),
+                _ => false,
+            };
+
+            if !self.keyword(K_AND) && !implicit {
+                return Ok(left);
+            }
+
+            left = Expr::And(Box::new(left), Box::new(self.not()?));
+        }
+    }
+
+    fn not(&mut self) -> Result<Expr, Error> {
+        match self.keyword(K_NOT) {
+            true => Ok(Expr::Not(Box::new(self.nested(Self::not)?))),
+            false => self.primary(),
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr, Error> {
+        let token = self.tokens.get(self.position).ok_or(Error::UnexpectedEnd)?;
+        self.position += 1;
+
+        match token {
+            Token::Open => {
+                let expr = self.nested(Self::or)?;
+
+                match self.peek() {
+                    Some(Token::Close) => {
+                        self.position += 1;
+                        Ok(expr)
+                    }
+                    Some(Token::Word(word, _)) => Err(Error::UnexpectedToken(word.clone())),
+                    _ => Err(Error::UnexpectedEnd),
+                }
+            }
+            Token::Close => Err(Error::UnexpectedToken(")".to_string())),
+            Token::Word(word, _) => self::term(word),
+        }
+    }
+}
+
+// Parses queries like `status:pending and (tag:work or prio>=high) and due<+7d`.
+// `not` binds tightest, then `and`, then `or`.
+pub fn parse(input: &str) -> Result<Expr, Error> {
+    let mut parser = Parser {
+        tokens: self::lex(input)?,
+        position: 0,
+        depth: 0,
+    };
+
+    let expr = parser.or()?;
+
+    match parser.peek() {
+        None => Ok(expr),
+        Some(Token::Close) => Err(Error::UnexpectedToken(")".to_string())),
+        Some(Token::Open) => Err(Error::UnexpectedToken("(".to_string())),
+        Some(Token::Word(word, _)) => Err(Error::UnexpectedToken(word.clone())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is(condition: Condition) -> Box<Expr> {
+        Box::new(Expr::Is(condition))
+    }
+
+    fn task(text: &str) -> Task {
+        Task::new(text.to_string())
+    }
+
+    #[test]
+    fn test_parse_terms() {
+        assert_eq!(
+            parse("status:done"),
+            Ok(Expr::Is(Condition::Status(TaskStatus::Done)))
+        );
+        assert_eq!(
+            parse("status:Waiting"),
+            Ok(Expr::Is(Condition::Status(TaskStatus::Waiting)))
+        );
+        assert_eq!(
+            parse("status!=pending"),
+            Ok(Expr::Not(is(Condition::Pending)))
+        );
+        assert_eq!(parse("status:Blocked"), Ok(Expr::Is(Condition::Blocked)));
+        assert_eq!(
+            parse("project:work"),
+            Ok(Expr::Is(Condition::Tag("+work".to_string())))
+        );
+        assert_eq!(
+            parse("text:\"Buy Milk\""),
+            Ok(Expr::Is(Condition::Text("buy milk".to_string())))
+        );
+        assert_eq!(
+            parse("prio>=high"),
+            Ok(Expr::Is(Condition::Priority(Op::Ge, Priority::High)))
+        );
+        assert_eq!(
+            parse("due<+7d"),
+            Ok(Expr::Is(Condition::Due(Op::Lt, Some("+7d".to_string()))))
+        );
+        assert_eq!(
+            parse("due:none"),
+            Ok(Expr::Is(Condition::Due(Op::Eq, None)))
+        );
+    }
+
+    #[test]
+    fn test_parse_precedence() {
+        let expected = Expr::Or(
+            Box::new(Expr::And(
+                is(Condition::Pending),
+                Box::new(Expr::Not(is(Condition::Tag("work".to_string())))),
+            )),
+            is(Condition::Priority(Op::Eq, Priority::High)),
+        );
+
+        assert_eq!(
+            parse("status:pending and not tag:work or prio:high"),
+            Ok(expected)
+        );
+
+        let expected = Expr::And(
+            is(Condition::Pending),
+            Box::new(Expr::Or(
+                is(Condition::Tag("work".to_string())),
+                is(Condition::Priority(Op::Ge, Priority::High)),
+            )),
+        );
+
+        assert_eq!(
+            parse("status:pending AND (tag:work OR prio>=high)"),
+            Ok(expected)
+        );
+        assert_eq!(
+            parse("status:pending (tag:work or prio>=high)"),
+            parse("status:pending and (tag:work or prio>=high)")
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(parse(""), Err(Error::UnexpectedEnd));
+        assert_eq!(parse("status:done and"), Err(Error::UnexpectedEnd));
+        assert_eq!(parse("(status:done"), Err(Error::UnexpectedEnd));
+        assert_eq!(
+            parse("status:done)"),
+            Err(Error::UnexpectedToken(")".to_string()))
+        );
+        assert_eq!(parse("text:\"milk"), Err(Error::UnterminatedQuote));
+        assert_eq!(
+            parse("work"),
+            Err(Error::MissingOperator("work".to_string()))
+        );
+        assert_eq!(
+            parse("tag<work"),
+            Err(Error::InvalidOperator("tag<work".to_string()))
+        );
+        assert_eq!(
+            parse("due>none"),
+            Err(Error::InvalidOperator("due>none".to_string()))
+        );
+        assert_eq!(
+            parse("size:big"),
+            Err(Error::UnknownField("size".to_string()))
+        );
+        assert_eq!(
+            parse("prio:urgent"),
+            Err(Error::InvalidValue("prio:urgent".to_string()))
+        );
+        assert_eq!(
+            parse("due:someday"),
+            Err(Error::InvalidValue("due:someday".to_string()))
+        );
+        assert_eq!(
+            parse("status:"),
+            Err(Error::InvalidValue("status:".to_string()))
+        );
+
+        let nested = |depth| format!("{}status:done{}", "(".repeat(depth), ")".repeat(depth));
+
+        assert!(parse(&nested(MAX_DEPTH)).is_ok());
+        assert_eq!(parse(&nested(MAX_DEPTH + 1)), Err(Error::TooDeep));
+        assert_eq!(parse(&nested(100_000)), Err(Error::TooDeep));
+        assert_eq!(
+            parse(&format!("{}status:done", "not ".repeat(100_000))),
+            Err(Error::TooDeep)
+        );
+    }
+
+    #[test]
+    fn test_matches() {
+        let today = Date::from_ymd(2026, 10, 18).unwrap();
+        let list = List::new();
+
+        let mut work = task("Write report +work @office");
+        work.priority = Priority::Medium;
+        work.due = today.add_days(3);
+
+        let mut home = task("Buy milk @home");
+        home.priority = Priority::High;
+        home.status = TaskStatus::Done;
+
+        let query = parse("status:pending and (tag:work or prio>=high) and due<+7d").unwrap();
+        assert!(query.matches(&work, &list, today));
+        assert!(!query.matches(&home, &list, today));
+
+        work.status = TaskStatus::Waiting;
+        assert!(query.matches(&work, &list, today));
+        assert!(parse("status:waiting")
+            .unwrap()
+            .matches(&work, &list, today));
+        assert!(!parse("status:todo").unwrap().matches(&work, &list, today));
+
+        let query = parse("tag:home or text:REPORT").unwrap();
+        assert!(query.matches(&work, &list, today));
+        assert!(query.matches(&home, &list, today));
+
+        let query = parse("context:office and due>=today and due!=none").unwrap();
+        assert!(query.matches(&work, &list, today));
+        assert!(!query.matches(&home, &list, today));
+
+        let query = parse("due:none").unwrap();
+        assert!(!query.matches(&work, &list, today));
+        assert!(query.matches(&home, &list, today));
+
+        let query = parse("not prio>medium").unwrap();
+        assert!(query.matches(&work, &list, today));
+        assert!(!query.matches(&home, &list, today));
+
+        let query = parse("due<today").unwrap();
+        assert!(!query.matches(&work, &list, today));
+        assert!(!query.matches(&home, &list, today));
+    }
+
+    #[test]
+    fn test_matches_blocked() {
+        let today = Date::from_ymd(2026, 10, 18).unwrap();
+        let mut list = List::new();
+        let _ = list.add("a".to_string());
+        let _ = list.add("b".to_string());
+        let _ = list.block(1, 0);
+
+        let query = parse("status:blocked").unwrap();
+        assert_eq!(
+            list.indices_filtered(|t| query.matches(t, &list, today)),
+            [1]
+        );
+
+        let _ = list.mark_done(&[0]);
+        assert!(list
+            .indices_filtered(|t| query.matches(t, &list, today))
+            .is_empty());
+    }
+}
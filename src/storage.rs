@@ -0,0 +1,383 @@
+use crate::{
+    actions::ActionResult,
+    config::{DATA_DIR_NAME, DATA_FILE_NAME, DEFAULT_LIST_NAME, ENV_FILE},
+    date::{self, Date},
+    models::list::{Format, List},
+};
+use std::{
+    env,
+    fs::{self, File},
+    io::{self, ErrorKind, Write},
+    path::{Path, PathBuf},
+};
+
+const BACKUP_SUFFIX: &str = ".bak";
+const TEMP_SUFFIX: &str = ".tmp";
+
+// `$XDG_DATA_HOME/todocli/list.json`, falling back to
+// `~/.local/share/todocli/list.json` and then to the current directory.
+fn resolve_data_file(xdg_data_home: Option<String>, home: Option<String>) -> PathBuf {
+    let base = match (xdg_data_home, home) {
+        (Some(xdg), _) if !xdg.is_empty() => PathBuf::from(xdg),
+        (_, Some(home)) if !home.is_empty() => Path::new(&home).join(".local").join("share"),
+        _ => PathBuf::from("."),
+    };
+
+    base.join(DATA_DIR_NAME).join(DATA_FILE_NAME)
+}
+
+pub fn data_file_path() -> String {
+    match env::var(ENV_FILE) {
+        Ok(path) => path,
+        Err(..) => resolve_data_file(env::var("XDG_DATA_HOME").ok(), env::var("HOME").ok())
+            .to_string_lossy()
+            .to_string(),
+    }
+}
+
+// The file of the list called `name`: `list.work.json` next to the data
+// file `list.json`. The default list is the data file itself.
+pub fn list_path(path: &str, name: Option<&str>) -> String {
+    let Some(name) = name else {
+        return path.to_string();
+    };
+
+    let target = Path::new(path);
+    let stem = target.file_stem().unwrap_or_default().to_string_lossy();
+
+    let file = match target.extension() {
+        Some(extension) => format!("{stem}.{name}.{}", extension.to_string_lossy()),
+        None => format!("{stem}.{name}"),
+    };
+
+    target.with_file_name(file).to_string_lossy().to_string()
+}
+
+pub fn is_list_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+}
+
+// Names of the lists stored next to the data file at `path`, sorted. The
+// default list is not among them, nor is the temporary file of a data file
+// without an extension (`notes.tmp`).
+pub fn lists(path: &str) -> Vec<String> {
+    let target = Path::new(path);
+
+    let dir = match target.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+
+    let prefix = format!(
+        "{}.",
+        target.file_stem().unwrap_or_default().to_string_lossy()
+    );
+    let suffix = match target.extension() {
+        Some(extension) => format!(".{}", extension.to_string_lossy()),
+        None => String::new(),
+    };
+
+    let mut result: Vec<String> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|e| e.ok())
+            .filter_map(|e| {
+                let file = e.file_name().to_string_lossy().to_string();
+                let name = file.strip_prefix(&prefix)?.strip_suffix(&suffix)?;
+                Some(name.to_string())
+            })
+            .filter(|n| {
+                self::is_list_name(n)
+                    && n != DEFAULT_LIST_NAME
+                    && (!suffix.is_empty() || n != &TEMP_SUFFIX[1..])
+            })
+            .collect(),
+        Err(..) => Vec::new(),
+    };
+
+    result.sort();
+    result
+}
+
+// Writes `contents` next to `path` first and renames it into place once
+// it is flushed to disk, so a crash never leaves a truncated file.
+pub fn write_atomic(path: &str, contents: &[u8]) -> io::Result<()> {
+    let target = Path::new(path);
+
+    if let Some(parent) = target.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut temp = target.as_os_str().to_owned();
+    temp.push(TEMP_SUFFIX);
+
+    let mut file = File::create(&temp)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+
+    fs::rename(&temp, target)
+}
+
+// `20261018-093005-120` for the given unix time in milliseconds.
+fn stamp(millis: u64) -> String {
+    let (year, month, day) = Date::from_timestamp(millis / 1000).ymd();
+    let seconds = (millis / 1000) % 86_400;
+
+    format!(
+        "{year:04}{month:02}{day:02}-{:02}{:02}{:02}-{:03}",
+        seconds / 3600,
+        seconds % 3600 / 60,
+        seconds % 60,
+        millis % 1000
+    )
+}
+
+// Backups of `path` (`<path>.<stamp>.bak` next to it), newest first.
+pub fn backups(path: &str) -> Vec<String> {
+    let target = Path::new(path);
+
+    let (dir, name) = match (target.parent(), target.file_name()) {
+        (Some(dir), Some(name)) if !dir.as_os_str().is_empty() => (dir, name),
+        (_, Some(name)) => (Path::new("."), name),
+        _ => return Vec::new(),
+    };
+
+    let prefix = format!("{}.", name.to_string_lossy());
+
+    let mut result: Vec<String> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|e| e.ok())
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .filter(|n| n.len() > prefix.len() + BACKUP_SUFFIX.len())
+            .filter(|n| n.starts_with(&prefix) && n.ends_with(BACKUP_SUFFIX))
+            .map(|n| dir.join(n).to_string_lossy().to_string())
+            .collect(),
+        Err(..) => Vec::new(),
+    };
+
+    result.sort();
+    result.reverse();
+    result
+}
+
+// Copies the current version of `path` aside and drops all but the
+// `keep` newest backups.
+fn rotate(path: &str, keep: usize) -> io::Result<()> {
+    if keep > 0 && Path::new(path).exists() {
+        let backup = format!("{path}.{}{BACKUP_SUFFIX}", self::stamp(date::now_millis()));
+        fs::copy(path, backup)?;
+    }
+
+    self::backups(path)
+        .iter()
+        .skip(keep)
+        .try_for_each(fs::remove_file)
+}
+
+// Atomic write that keeps the `keep` previous versions as backups.
+// Unchanged contents are not written again, so no backup is wasted.
+pub fn write_with_backups(path: &str, contents: &[u8], keep: usize) -> io::Result<()> {
+    if fs::read(path).is_ok_and(|current| current == contents) {
+        return Ok(());
+    }
+
+    self::rotate(path, keep)?;
+    self::write_atomic(path, contents)
+}
+
+// A missing file is an empty list rather than an error.
+pub fn load(path: &str, list: &mut List) -> ActionResult {
+    match fs::read_to_string(path) {
+        Ok(contents) => match list.deserialize(&contents, Format::from_path(path)) {
+            Ok(..) => ActionResult::Ok,
+            Err(..) => ActionResult::FileReadError,
+        },
+        Err(e) if e.kind() == ErrorKind::NotFound => ActionResult::Ok,
+        Err(..) => ActionResult::CannotLoad,
+    }
+}
+
+pub fn save(path: &str, list: &List, backups: usize) -> ActionResult {
+    let contents = list.serialize(Format::from_path(path));
+
+    match self::write_with_backups(path, contents.as_bytes(), backups) {
+        Ok(..) => ActionResult::Ok,
+        Err(..) => ActionResult::CannotSave,
+    }
+}
+
+// Deletes the list file at `path`, keeping its last version as a backup.
+pub fn delete(path: &str, keep: usize) -> ActionResult {
+    match self::rotate(path, keep).and_then(|_| fs::remove_file(path)) {
+        Ok(..) => ActionResult::Ok,
+        Err(..) => ActionResult::CannotSave,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("todocli_storage_{name}"));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_resolve_data_file() {
+        assert_eq!(
+            resolve_data_file(Some("/xdg".to_string()), Some("/home/u".to_string())),
+            PathBuf::from("/xdg/todocli/list.json")
+        );
+        assert_eq!(
+            resolve_data_file(Some("".to_string()), Some("/home/u".to_string())),
+            PathBuf::from("/home/u/.local/share/todocli/list.json")
+        );
+        assert_eq!(
+            resolve_data_file(None, None),
+            PathBuf::from("./todocli/list.json")
+        );
+    }
+
+    #[test]
+    fn test_list_path() {
+        assert_eq!(list_path("/d/list.json", None), "/d/list.json");
+        assert_eq!(list_path("/d/list.json", Some("work")), "/d/list.work.json");
+        assert_eq!(list_path("todo.txt", Some("work")), "todo.work.txt");
+        assert_eq!(list_path("/d/todo", Some("work")), "/d/todo.work");
+    }
+
+    #[test]
+    fn test_lists() {
+        let dir = temp_dir("lists");
+        let _ = fs::create_dir_all(&dir);
+        let path = dir.join("list.json").to_string_lossy().to_string();
+
+        [
+            "list.json",
+            "list.work.json",
+            "list.groceries.json",
+            "list.default.json",
+            "list.work.json.1.bak",
+            "list.work.json.tmp",
+            "list.a b.json",
+            "other.home.json",
+            "list.home.txt",
+        ]
+        .iter()
+        .for_each(|name| {
+            let _ = fs::write(dir.join(name), "");
+        });
+
+        assert_eq!(lists(&path), ["groceries", "work"]);
+
+        let work = list_path(&path, Some("work"));
+        assert_eq!(delete(&work, 0), ActionResult::Ok);
+        assert_eq!(lists(&path), ["groceries"]);
+        assert_eq!(delete(&work, 0), ActionResult::CannotSave);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_lists_without_extension() {
+        let dir = temp_dir("lists_without_extension");
+        let _ = fs::create_dir_all(&dir);
+        let path = dir.join("notes").to_string_lossy().to_string();
+
+        ["notes", "notes.tmp", "notes.work", "notes.work.tmp"]
+            .iter()
+            .for_each(|name| {
+                let _ = fs::write(dir.join(name), "");
+            });
+
+        assert_eq!(lists(&path), ["work"]);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_write_atomic() {
+        let dir = temp_dir("write_atomic");
+        let path = dir.join("nested").join("list.json");
+        let path = path.to_string_lossy().to_string();
+
+        assert!(write_atomic(&path, b"first").is_ok());
+        assert!(write_atomic(&path, b"second").is_ok());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second");
+        assert!(fs::metadata(format!("{path}.tmp")).is_err());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_save_load() {
+        let dir = temp_dir("save_load");
+        let path = dir.join("list.json").to_string_lossy().to_string();
+
+        let mut list = List::new();
+        assert_eq!(load(&path, &mut list), ActionResult::Ok);
+        assert!(list.is_empty());
+
+        let _ = list.add("test".to_string());
+        assert_eq!(save(&path, &list, 0), ActionResult::Ok);
+
+        let mut loaded = List::new();
+        assert_eq!(load(&path, &mut loaded), ActionResult::Ok);
+        assert!(loaded.get(0).unwrap().text == "test");
+
+        let _ = fs::write(&path, "not json");
+        assert_eq!(load(&path, &mut loaded), ActionResult::FileReadError);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_stamp() {
+        assert_eq!(stamp(0), "19700101-000000-000");
+        assert_eq!(stamp(1_792_315_805_120), "20261018-093005-120");
+    }
+
+    #[test]
+    fn test_write_with_backups() {
+        let dir = temp_dir("write_with_backups");
+        let path = dir.join("list.txt").to_string_lossy().to_string();
+
+        for i in 0..5 {
+            assert!(write_with_backups(&path, format!("v{i}").as_bytes(), 3).is_ok());
+            std::thread::sleep(std::time::Duration::from_millis(2));
+        }
+
+        let result = backups(&path);
+        assert_eq!(result.len(), 3);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "v4");
+        assert_eq!(fs::read_to_string(&result[0]).unwrap(), "v3");
+        assert_eq!(fs::read_to_string(&result[2]).unwrap(), "v1");
+
+        assert!(write_with_backups(&path, b"v4", 3).is_ok());
+        assert_eq!(backups(&path), result);
+
+        assert!(write_with_backups(&path, b"v5", 0).is_ok());
+        assert!(backups(&path).is_empty());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_backups_ignores_other_files() {
+        let dir = temp_dir("backups_other");
+        let _ = fs::create_dir_all(&dir);
+        let path = dir.join("list.txt").to_string_lossy().to_string();
+
+        let _ = fs::write(dir.join("list.txt.bak"), "");
+        let _ = fs::write(dir.join("other.txt.1.bak"), "");
+        let _ = fs::write(dir.join("list.txt.1.bak"), "");
+
+        assert_eq!(backups(&path).len(), 1);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
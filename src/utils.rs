@@ -1,14 +1,16 @@
-use regex::Regex;
-use std::io::{self, Write};
-
-pub fn are_strings_integers(strings: &Vec<&str>) -> bool {
-    let re = Regex::new(r"[1-9]+").unwrap();
-    strings.iter().all(|s| re.is_match(s))
-}
-
-pub fn trim_str(target: &str) -> String {
-    let entries: Vec<&str> = target.split_whitespace().collect();
-    entries.join(" ")
+use std::{
+    env,
+    io::{self, Write},
+};
+
+// Expands a leading `~` to the home directory, as a shell would.
+pub fn expand_home(path: &str) -> String {
+    match (path.strip_prefix('~'), env::var("HOME")) {
+        (Some(rest), Ok(home)) if rest.is_empty() || rest.starts_with('/') => {
+            format!("{home}{rest}")
+        }
+        _ => path.to_string(),
+    }
 }
 
 pub fn get_user_input() -> String {
@@ -27,17 +29,15 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_are_strings_integers_true() {
-        assert!(are_strings_integers(&vec!["1", "2", "42"]));
-    }
+    fn test_expand_home() {
+        let home = env::var("HOME").unwrap_or_default();
 
-    #[test]
-    fn test_is_strings_integers_false() {
-        assert!(!are_strings_integers(&vec!["1", "two", "42"]));
-    }
+        if !home.is_empty() {
+            assert_eq!(expand_home("~/todo.txt"), format!("{home}/todo.txt"));
+            assert_eq!(expand_home("~"), home);
+        }
 
-    #[test]
-    fn test_trim_str() {
-        assert_eq!(trim_str("    hello   world!  "), "hello world!");
+        assert_eq!(expand_home("~user/todo.txt"), "~user/todo.txt");
+        assert_eq!(expand_home("./todo.txt"), "./todo.txt");
     }
 }